@@ -6,6 +6,7 @@ import androidx.compose.runtime.getValue
 import androidx.compose.runtime.mutableStateOf
 import androidx.compose.runtime.setValue
 import androidx.lifecycle.viewModelScope
+import com.glennwso.cruxoflife.shared.handleResponse
 import com.glennwso.cruxoflife.shared.processEvent
 import com.glennwso.cruxoflife.shared.view
 import com.glennwso.cruxoflife.shared_types.AlertOpereation
@@ -18,6 +19,7 @@ import com.glennwso.cruxoflife.shared_types.ViewModel
 import io.ktor.client.HttpClient
 import io.ktor.client.engine.cio.CIO
 import io.ktor.client.engine.cio.endpoint
+import kotlinx.coroutines.delay
 import kotlinx.coroutines.launch
 
 class Core : androidx.lifecycle.ViewModel() {
@@ -66,8 +68,11 @@ class Core : androidx.lifecycle.ViewModel() {
                     is AlertOpereation.Info -> {
                         this.alert = alert.value
                     }
-                    else -> {
-                        this.alert = "unknown Alert kind"
+                    is AlertOpereation.Warning -> {
+                        this.alert = "Warning: " + alert.value
+                    }
+                    is AlertOpereation.Error -> {
+                        this.alert = "Error: " + alert.value
                     }
                 }
             }
@@ -78,7 +83,15 @@ class Core : androidx.lifecycle.ViewModel() {
                     }
                 }
             }
-
+            is Effect.Timer -> {
+                viewModelScope.launch {
+                    delay(effect.value.millis)
+                    val effects = handleResponse(request.id.toUInt(), byteArrayOf())
+                    for (request in Requests.bincodeDeserialize(effects)) {
+                        processEffect(request)
+                    }
+                }
+            }
         }
     }
 }
\ No newline at end of file
@@ -47,12 +47,14 @@ import androidx.compose.ui.geometry.Offset
 import androidx.compose.ui.geometry.Size
 import androidx.compose.ui.graphics.Color
 import androidx.compose.ui.graphics.ColorFilter
+import androidx.compose.ui.graphics.Path
 import androidx.compose.ui.input.pointer.pointerInput
 import androidx.compose.ui.tooling.preview.Preview
 import androidx.compose.ui.unit.dp
 import androidx.core.app.ActivityCompat.startActivityForResult
 import androidx.lifecycle.viewmodel.compose.viewModel
 import com.glennwso.cruxoflife.shared_types.Event
+import com.glennwso.cruxoflife.shared_types.Layout
 import com.glennwso.cruxoflife.ui.theme.CounterTheme
 import com.google.accompanist.permissions.ExperimentalPermissionsApi
 import kotlinx.coroutines.launch
@@ -157,7 +159,7 @@ class MainActivity : ComponentActivity() {
     }
 
 @Composable
-fun LifeGrid(activity: Activity?, core: Core = viewModel(), running: Boolean){
+fun LifeGrid(activity: Activity?, core: Core = viewModel()){
 //    var cameraOffset by remember { mutableStateOf(Offset.Zero) }
     var zoom by remember { mutableFloatStateOf(1f) }
 
@@ -188,24 +190,45 @@ fun LifeGrid(activity: Activity?, core: Core = viewModel(), running: Boolean){
             })
         } ) {
         cSize = size
-        if (running) {
-            coroutineScope.launch { core.update(Event.Step()) }
-        }
         val cells = core.view?.cell_coords ?: listOf()
+        val states = core.view?.cell_states ?: listOf()
         val cellSize = core.view?.cell_size ?: 30f
-        cells.forEach { cell ->
-
-            drawRect(
-                color = Color.Red,
-                size = Size(1f, 1f) * cellSize,
-                topLeft = Offset(
-                    x = cell[0],
-                    y = cell[1]
+        val hexagons = core.view?.layout is Layout.Hexagonal
+        val hexHeight = cellSize * 2f / kotlin.math.sqrt(3f)
+        val palette = core.view?.palette ?: listOf()
+        cells.forEachIndexed { i, cell ->
+            // decaying cells of generations rules fade as they age, rule tables
+            // may colour each state
+            val state = states.getOrNull(i)?.toInt() ?: 1
+            val color = palette.getOrNull(state)?.let { (r, g, b) ->
+                Color(r.toInt(), g.toInt(), b.toInt())
+            } ?: Color.Red.copy(alpha = maxOf(0.2f, 1f - 0.15f * (state - 1)))
+            if (hexagons) {
+                // pointy topped hexagon in the box at the cell coordinate
+                val (x, y) = cell
+                val hexagon = Path().apply {
+                    moveTo(x + cellSize / 2f, y)
+                    lineTo(x + cellSize, y + hexHeight / 4f)
+                    lineTo(x + cellSize, y + hexHeight * 3f / 4f)
+                    lineTo(x + cellSize / 2f, y + hexHeight)
+                    lineTo(x, y + hexHeight * 3f / 4f)
+                    lineTo(x, y + hexHeight / 4f)
+                    close()
+                }
+                drawPath(hexagon, color = color)
+            } else {
+                drawRect(
+                    color = color,
+                    size = Size(1f, 1f) * cellSize,
+                    topLeft = Offset(
+                        x = cell[0],
+                        y = cell[1]
+                    )
                 )
-            )
+            }
         }
         // draw cell borders
-        if (zoom > 0.4) {
+        if (zoom > 0.4 && !hexagons) {
             val nCols = (w / cellSize).roundToInt()
             val nRows = (h / cellSize).roundToInt()
 
@@ -242,11 +265,11 @@ fun LifeGrid(activity: Activity?, core: Core = viewModel(), running: Boolean){
 fun View(activity: Activity?, core: Core = viewModel()) {
     val coroutineScope = rememberCoroutineScope()
 
-    var running by remember { mutableStateOf(false) }
-    var runText  = "Run"
-    if (running){
-        runText = "Running"
-    }
+    // the core runs the world on its own timer, the buttons only start and
+    // stop it and pick its speed
+    val running = core.view?.running ?: false
+    val speed = core.view?.speed ?: 60f
+    val speeds = listOf(1f, 10f, 60f, 1000f)
     val snackbarHostState = remember { SnackbarHostState() }
 
     Scaffold(
@@ -263,8 +286,8 @@ fun View(activity: Activity?, core: Core = viewModel()) {
                 actions = {
                     Button(
                         onClick = {
-                            running = false
                             coroutineScope.launch {
+                                core.update(Event.Pause())
                                 core.update(Event.SaveWorld())
                                 createFile(activity!!, Uri.EMPTY)
                             }
@@ -273,8 +296,8 @@ fun View(activity: Activity?, core: Core = viewModel()) {
                     Spacer(modifier = Modifier.width(10.dp))
                     Button(
                         onClick = {
-                            running = false
                             coroutineScope.launch {
+                                core.update(Event.Pause())
                                 readFile(activity!!, Uri.EMPTY)
                             }
                         }
@@ -292,9 +315,22 @@ fun View(activity: Activity?, core: Core = viewModel()) {
             ) {
 
                 Button(onClick = {
-                    running = !running
+                    coroutineScope.launch {
+                        core.update(if (running) Event.Pause() else Event.Run())
+                    }
                 }){
-                    Text(runText)
+                    Text(if (running) "Stop" else "Run")
+                }
+
+                Button(
+                    modifier = Modifier.padding(start = 15.dp),
+                    onClick = {
+                        // next of the preset speeds, back to the slowest after the fastest
+                        val next = speeds.firstOrNull { it > speed } ?: speeds.first()
+                        coroutineScope.launch { core.update(Event.SetSpeed(next)) }
+                    }
+                ){
+                    Text("${speed.roundToInt()}/s")
                 }
 
                 Button(
@@ -302,9 +338,7 @@ fun View(activity: Activity?, core: Core = viewModel()) {
                     onClick = {
                         coroutineScope.launch {
                             core.update(Event.Step())
-                            // Request code for creating a PDF document.
                         }
-                        running = false
                     }, colors = ButtonDefaults.buttonColors(
                         containerColor = Color.hsl(348F, 0.86F, 0.61F)
                     )
@@ -320,7 +354,7 @@ fun View(activity: Activity?, core: Core = viewModel()) {
                 .padding(innerPadding).fillMaxSize(),
             verticalArrangement = Arrangement.spacedBy(16.dp),
         ){
-            LifeGrid(activity, core, running)
+            LifeGrid(activity, core)
         }
 
     }
@@ -0,0 +1,85 @@
+//! Which cells count as neighbours, chosen with a suffix on the rulestring as
+//! in Golly: `B2/S34H` is hexagonal and `B2/S3V` von Neumann
+//!
+//! Hexagons use axial coordinates, [row, column] with every row shifted half a
+//! cell to the right of the one above, so the neighbours of a cell are its
+//! Moore neighbours without the top left and bottom right ones.
+//!
+//! Neighbourhoods reaching further than the adjacent cells, extended-range
+//! Moore included, are Larger than Life rules like `R2,C0,M0,S2..3,B3..3,NM`
+//! rather than a suffix here, see [super::ltl].
+use serde::{Deserialize, Serialize};
+
+use super::{hensel, CellCoord};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Adjacency {
+    /// the 8 surrounding cells
+    #[default]
+    Moore,
+    /// the 4 orthogonal cells, `V`
+    VonNeumann,
+    /// the 6 cells around a hexagon, `H`
+    Hexagonal,
+}
+
+/// How the shells draw cells and map clicks back to them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// `cell_size` squares
+    #[default]
+    Square,
+    /// pointy topped hexagons `cell_size` wide and `cell_size * 2 / sqrt(3)`
+    /// high, each cell coordinate is the top left of the box around one
+    Hexagonal,
+}
+
+const VON_NEUMANN: [CellCoord; 4] = [[-1, 0], [0, -1], [0, 1], [1, 0]];
+const HEXAGONAL: [CellCoord; 6] = [[-1, 0], [-1, 1], [0, -1], [0, 1], [1, -1], [1, 0]];
+
+impl Adjacency {
+    /// [row, column] of each neighbour relative to the cell, bit `i` of a
+    /// [hensel::Neighbourhood] stands for the `i`th one
+    pub const fn offsets(&self) -> &'static [CellCoord] {
+        match self {
+            Adjacency::Moore => &hensel::OFFSETS,
+            Adjacency::VonNeumann => &VON_NEUMANN,
+            Adjacency::Hexagonal => &HEXAGONAL,
+        }
+    }
+    pub const fn layout(&self) -> Layout {
+        match self {
+            Adjacency::Moore | Adjacency::VonNeumann => Layout::Square,
+            Adjacency::Hexagonal => Layout::Hexagonal,
+        }
+    }
+    pub const fn suffix(&self) -> &'static str {
+        match self {
+            Adjacency::Moore => "",
+            Adjacency::VonNeumann => "V",
+            Adjacency::Hexagonal => "H",
+        }
+    }
+    /// the neighbourhood of a trailing `H` or `V`, and the rest of `rulestring`
+    pub fn strip_suffix(rulestring: &str) -> (&str, Self) {
+        for adjacency in [Adjacency::VonNeumann, Adjacency::Hexagonal] {
+            let suffix = adjacency.suffix();
+            let lower = suffix.to_ascii_lowercase();
+            if let Some(rest) = rulestring
+                .strip_suffix(suffix)
+                .or_else(|| rulestring.strip_suffix(lower.as_str()))
+            {
+                return (rest, adjacency);
+            }
+        }
+        (rulestring, Adjacency::Moore)
+    }
+    /// `rulestring` ends with a range like `R2` or a neighbourhood like `NM`,
+    /// which only Larger than Life rules have
+    pub fn is_range_suffix(rulestring: &str) -> bool {
+        let rest = rulestring.trim_end_matches(|c: char| c.is_ascii_digit());
+        let mut end = rulestring.chars().rev();
+        (rest.len() < rulestring.len() && rest.ends_with('R'))
+            || matches!((end.next(), end.next()), (Some(c), Some('N')) if c.is_ascii_uppercase())
+    }
+}
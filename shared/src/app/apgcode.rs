@@ -0,0 +1,223 @@
+//! Apgcodes naming still lifes, oscillators and spaceships like apgsearch
+//! and Catagolue do, such as `xs4_33` for the block
+//!
+//! The code is the kind and period of the object, `xs` with its population
+//! for still lifes, `xp` and `xq` with their periods for oscillators and
+//! spaceships, followed by its cells in the extended Wechsler format: strips
+//! of 5 rows separated by `z`, each column of a strip one character for its
+//! cells, the top one lowest, and `w`, `x` and `y` shortening runs of empty
+//! columns. Of all the phases and orientations the shortest code, then the
+//! first in order, is the canonical one.
+use std::collections::BTreeMap;
+
+use super::{
+    format::{LoadError, MAX_CELLS},
+    CellCoord, CellVector, Rotation,
+};
+
+/// characters of the columns of a strip, and of runs of 4 and more empty
+/// columns after `y`
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// rows of a strip
+const STRIP: i32 = 5;
+
+/// Kind of object and its period, the start of its apgcode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// still life of some population
+    StillLife(usize),
+    Oscillator(u64),
+    Spaceship(u64),
+}
+
+impl Kind {
+    fn prefix(&self) -> String {
+        match self {
+            Kind::StillLife(population) => format!("xs{population}_"),
+            Kind::Oscillator(period) => format!("xp{period}_"),
+            Kind::Spaceship(period) => format!("xq{period}_"),
+        }
+    }
+}
+
+/// `cells` in the extended Wechsler format, from the top left of their
+/// bounding box
+fn wechsler(cells: &[CellCoord]) -> String {
+    let (Some(top), Some(left)) = (
+        cells.iter().map(|[row, _]| *row).min(),
+        cells.iter().map(|[_, col]| *col).min(),
+    ) else {
+        return String::new();
+    };
+    // columns of each strip, kept sparse as far apart cells only take runs
+    let mut columns: BTreeMap<(i32, i32), usize> = BTreeMap::new();
+    for [row, col] in cells {
+        let strip = (row - top) / STRIP;
+        *columns.entry((strip, col - left)).or_default() |= 1 << ((row - top) % STRIP);
+    }
+    let mut code = String::new();
+    let mut at = (0, 0);
+    for ((strip, col), column) in columns {
+        for _ in at.0..strip {
+            code.push('z');
+            at = (strip, 0);
+        }
+        let mut empty = (col - at.1) as usize;
+        while empty > 0 {
+            let run = empty.min(39);
+            match run {
+                1 => code.push('0'),
+                2 => code.push('w'),
+                3 => code.push('x'),
+                _ => {
+                    code.push('y');
+                    code.push(char::from(DIGITS[run - 4]));
+                }
+            }
+            empty -= run;
+        }
+        code.push(char::from(DIGITS[column]));
+        at = (strip, col + 1);
+    }
+    code
+}
+
+/// `cells` mirrored left to right or not, then turned by `rotation`
+fn orient(cells: &[CellCoord], rotation: Rotation, mirror: bool) -> Vec<CellCoord> {
+    cells
+        .iter()
+        .map(|&[row, col]| rotation.rotate(if mirror { [row, -col] } else { [row, col] }))
+        .collect()
+}
+
+/// apgcode of the `phases` of an object of `kind`, the least over all of
+/// them and their orientations. Patterns that are not objects get the `zz_`
+/// prefix apgsearch gives what it can not classify.
+pub fn encode(kind: Option<Kind>, phases: &[CellVector]) -> String {
+    let rotations = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270];
+    let cells = phases.iter().flat_map(|cells| {
+        rotations.iter().flat_map(move |rotation| {
+            [false, true].map(|mirror| wechsler(&orient(cells, *rotation, mirror)))
+        })
+    });
+    let cells = cells
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default();
+    let prefix = kind.map_or_else(|| "zz_".to_string(), |kind| kind.prefix());
+    prefix + &cells
+}
+
+/// the text is a single apgcode, such as `xq4_153`, and not an rle header
+pub fn recognizes(text: &str) -> bool {
+    let Some((prefix, _)) = text.trim().split_once('_') else {
+        return false;
+    };
+    match prefix.as_bytes() {
+        [b'x', b's' | b'p' | b'q', period @ ..] => {
+            !period.is_empty() && period.iter().all(u8::is_ascii_digit)
+        }
+        _ => prefix == "zz",
+    }
+}
+
+/// cells of an apgcode, centered on the origin like rle patterns
+pub fn decode(code: &str) -> Result<CellVector, LoadError> {
+    let invalid = || LoadError::Syntax(format!("invalid apgcode {code}"));
+    let (_, wechsler) = code.trim().split_once('_').ok_or_else(invalid)?;
+    let digit = |c: char| DIGITS.iter().position(|digit| char::from(*digit) == c);
+    let (mut strip, mut col) = (0, 0);
+    let mut cells = Vec::new();
+    let mut chars = wechsler.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            'z' => (strip, col) = (strip + 1, 0),
+            'w' => col += 2,
+            'x' => col += 3,
+            'y' => col += 4 + chars.next().and_then(digit).ok_or_else(invalid)? as i32,
+            c => {
+                let column = digit(c).filter(|column| *column < 32).ok_or_else(invalid)?;
+                let rows = (0..STRIP).filter(|row| column & 1 << row != 0);
+                cells.extend(rows.map(|row| [strip * STRIP + row, col]));
+                col += 1;
+            }
+        }
+        if cells.len() > MAX_CELLS {
+            return Err(LoadError::TooManyCells(cells.len()));
+        }
+    }
+    let height = cells.iter().map(|[row, _]| row + 1).max().unwrap_or(0);
+    let width = cells.iter().map(|[_, col]| col + 1).max().unwrap_or(0);
+    let (top, left) = (-height / 2, -width / 2);
+    Ok(cells
+        .into_iter()
+        .map(|[row, col]| [row + top, col + left])
+        .collect())
+}
+
+#[cfg(test)]
+mod test_apgcode {
+    use super::*;
+    use crate::app::{Life, Rule};
+
+    /// the cells of every phase of an object starting from `life`
+    fn phases(life: &Life, period: u64) -> Vec<Vec<CellCoord>> {
+        let mut life = life.clone();
+        (0..period)
+            .map(|_| {
+                let cells = life.state_as_list();
+                life.tick(&Rule::default());
+                cells
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode() {
+        let block = vec![[0, 0], [0, 1], [1, 0], [1, 1]];
+        assert_eq!(encode(Some(Kind::StillLife(4)), &[block]), "xs4_33");
+        let tub = Life::tub().state_as_list();
+        assert_eq!(encode(Some(Kind::StillLife(4)), &[tub]), "xs4_252");
+        let blinker = phases(&Life::blinker(), 2);
+        assert_eq!(encode(Some(Kind::Oscillator(2)), &blinker), "xp2_7");
+        let glider = phases(&Life::glider(), 4);
+        assert_eq!(encode(Some(Kind::Spaceship(4)), &glider), "xq4_153");
+    }
+
+    #[test]
+    /// decoded objects encode back to the same apgcode in their rule
+    fn test_decode() {
+        let rule = Rule::default();
+        for code in [
+            "xs4_33",
+            "xs6_696",
+            "xp2_7",
+            "xq4_153",
+            "xp15_4r4z4r4",
+            "xs8_rr",
+        ] {
+            let life = Life::new(&decode(code).unwrap());
+            assert_eq!(life.apgcode(&rule), code);
+        }
+        let mut block = decode("xs4_33").unwrap();
+        block.sort_unstable();
+        assert_eq!(block, [[-1, -1], [-1, 0], [0, -1], [0, 0]]);
+        assert_eq!(decode("zz_1yz01").unwrap().len(), 2);
+        for code in ["xs4", "xs4_3y", "xs4_3!", "xs4_3Z"] {
+            assert!(matches!(decode(code), Err(LoadError::Syntax(_))), "{code}");
+        }
+        assert!(recognizes(" xq4_153\n") && recognizes("zz_1"));
+        for text in ["x = 3, y = 1", "x_1", "xs_1", "xr4_1", "#C xs4_33"] {
+            assert!(!recognizes(text), "{text}");
+        }
+    }
+
+    #[test]
+    /// strips of 5 rows and runs of empty columns
+    fn test_wechsler() {
+        assert_eq!(wechsler(&[[0, 0], [6, 0]]), "1z2");
+        assert_eq!(wechsler(&[[0, 0], [0, 3], [0, 7], [0, 12]]), "1w1x1y01");
+        assert_eq!(wechsler(&[[0, 0], [12, 0]]), "1zz4");
+        assert_eq!(wechsler(&[[0, 0], [0, 41], [4, 0]]), "hyz01");
+    }
+}
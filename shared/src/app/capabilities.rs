@@ -1,15 +1,15 @@
 use crux_core::capability::{CapabilityContext, Operation};
 use crux_core::macros::Capability;
 use serde::{Deserialize, Serialize};
-use serde_json::to_vec;
 
-use super::{CellVector, Model};
+use super::CellVector;
 
 type Data = Vec<u8>;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum ExportOperation {
-    Save(Data),
+    /// file name and contents
+    Save(String, Data),
     Copy(Data),
 }
 
@@ -26,25 +26,22 @@ impl<Event> FileIO<Event> {
     pub fn new(context: CapabilityContext<ExportOperation, Event>) -> Self {
         Self { context }
     }
-    pub fn save(&self, model: &Model)
+    pub fn save(&self, file_name: String, data: Data)
     where
         Event: 'static,
     {
         let ctx = self.context.clone();
-        let data = model.life.state_as_list();
-        let data = to_vec(&data).unwrap();
         self.context.spawn(async move {
             // Instruct Shell to save some bytes of data
-            ctx.request_from_shell(ExportOperation::Save(data)).await;
+            ctx.request_from_shell(ExportOperation::Save(file_name, data))
+                .await;
         })
     }
-    pub fn copy(&self, model: &Model)
+    pub fn copy(&self, data: Data)
     where
         Event: 'static,
     {
         let ctx = self.context.clone();
-        let data = model.life.state_as_list();
-        let data = to_vec(&data).unwrap();
         self.context.spawn(async move {
             // Instruct Shell to save some bytes of data
             ctx.request_from_shell(ExportOperation::Copy(data)).await;
@@ -72,7 +69,7 @@ impl<Event> Alert<Event> {
     pub fn new(context: CapabilityContext<AlertOpereation, Event>) -> Self {
         Self { context }
     }
-    pub fn info(&self, msg: String)
+    fn alert(&self, operation: AlertOpereation)
     where
         Event: 'static,
     {
@@ -80,7 +77,57 @@ impl<Event> Alert<Event> {
         // Start a shell interaction
         self.context.spawn(async move {
             // Instruct Shell to get ducks in a row and await the ducks
-            ctx.request_from_shell(AlertOpereation::Info(msg)).await;
+            ctx.request_from_shell(operation).await;
+        })
+    }
+    pub fn info(&self, msg: String)
+    where
+        Event: 'static,
+    {
+        self.alert(AlertOpereation::Info(msg))
+    }
+    pub fn warning(&self, msg: String)
+    where
+        Event: 'static,
+    {
+        self.alert(AlertOpereation::Warning(msg))
+    }
+    pub fn error(&self, msg: String)
+    where
+        Event: 'static,
+    {
+        self.alert(AlertOpereation::Error(msg))
+    }
+}
+
+/// wake the core up after `millis` milliseconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TimerOperation {
+    pub millis: u64,
+}
+
+impl Operation for TimerOperation {
+    type Output = ();
+}
+
+#[derive(Capability)]
+pub struct Timer<Event> {
+    context: CapabilityContext<TimerOperation, Event>,
+}
+
+impl<Event> Timer<Event> {
+    pub fn new(context: CapabilityContext<TimerOperation, Event>) -> Self {
+        Self { context }
+    }
+    /// send `event` to the app once `millis` have passed
+    pub fn notify_after(&self, millis: u64, event: Event)
+    where
+        Event: Send + 'static,
+    {
+        let ctx = self.context.clone();
+        self.context.spawn(async move {
+            ctx.request_from_shell(TimerOperation { millis }).await;
+            ctx.update_app(event);
         })
     }
 }
@@ -0,0 +1,223 @@
+//! Census of the objects in a world, like the one apgsearch takes of what its
+//! soups leave behind
+//!
+//! The live cells are split into islands of cells at most `gap` dead cells
+//! apart, then each island is run on its own until it repeats and is named by
+//! its apgcode. Islands that do not go back to how they started, such as ones
+//! still evolving or touching others, are counted as pathological.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    apgcode::{self, Kind},
+    cycle::Cycles,
+    library::{self, Category},
+    CellCoord, CellVector, Life, Rule,
+};
+
+/// most dead cells between the cells of one island
+pub const MAX_GAP: u32 = 8;
+
+/// generations an island is run for to find its period
+const MAX_GENERATIONS: u64 = 1 << 10;
+
+/// what apgsearch calls objects it could not classify
+const PATHOLOGICAL: &str = "PATHOLOGICAL";
+
+/// Objects of one apgcode found by a census
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Tally {
+    pub apgcode: String,
+    /// name of the library pattern with this apgcode, only known for the
+    /// default rule
+    pub name: Option<String>,
+    /// still life, oscillator or spaceship, none for pathological objects
+    pub category: Option<Category>,
+    pub count: u64,
+}
+
+/// Objects counted by [Event::Census](super::Event::Census)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Census {
+    /// generation the census was taken at
+    pub generation: u64,
+    /// most common objects first
+    pub objects: Vec<Tally>,
+}
+
+/// tally such as "12×Block, 5×Blinker, 2×Glider"
+impl Display for Census {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let objects: Vec<String> = (self.objects.iter())
+            .map(|tally| {
+                let name = tally.name.as_ref().unwrap_or(&tally.apgcode);
+                format!("{}×{name}", tally.count)
+            })
+            .collect();
+        write!(f, "{}", objects.join(", "))
+    }
+}
+
+/// the rule keeps no more than its live cells from one generation to the
+/// next, so objects can be run on their own
+pub fn runs(rule: &Rule) -> bool {
+    rule.states() == 2 && rule.wolfram().is_none() && rule.margolus().is_none()
+}
+
+/// `cells` split into groups of cells at most `gap` dead cells apart
+fn islands(cells: &[CellCoord], gap: u32) -> Vec<CellVector> {
+    let reach = gap.min(MAX_GAP) as i32 + 1;
+    let mut left: HashSet<CellCoord> = cells.iter().copied().collect();
+    let mut islands = Vec::new();
+    while let Some(&start) = left.iter().next() {
+        left.remove(&start);
+        let mut island = vec![start];
+        let mut next = 0;
+        while let Some(&[row, col]) = island.get(next) {
+            next += 1;
+            for dr in -reach..=reach {
+                for dc in -reach..=reach {
+                    if left.remove(&[row + dr, col + dc]) {
+                        island.push([row + dr, col + dc]);
+                    }
+                }
+            }
+        }
+        islands.push(island);
+    }
+    islands
+}
+
+/// kind and phases of the object `cells` make when run on their own with
+/// `rule`, none when they do not return to how they started
+pub fn classify(cells: &[CellCoord], rule: &Rule) -> Option<(Kind, Vec<CellVector>)> {
+    let mut life = Life::new(cells);
+    let mut cycles = Cycles::default();
+    let mut phases = Vec::new();
+    for generation in 0..=MAX_GENERATIONS {
+        let fingerprint = life.fingerprint();
+        cycles.observe(rule, generation, fingerprint);
+        if let Some(cycle) = cycles.found(rule, generation, fingerprint) {
+            let kind = match (cycle.period, cycle.displacement) {
+                (_, _) if cycle.generation != 0 => return None,
+                (1, [0, 0]) => Kind::StillLife(cells.len()),
+                (period, [0, 0]) => Kind::Oscillator(period),
+                (period, _) => Kind::Spaceship(period),
+            };
+            return Some((kind, phases));
+        }
+        phases.push(life.state_as_list());
+        life.tick(rule);
+    }
+    None
+}
+
+const fn category(kind: Kind) -> Category {
+    match kind {
+        Kind::StillLife(_) => Category::StillLife,
+        Kind::Oscillator(_) => Category::Oscillator,
+        Kind::Spaceship(_) => Category::Spaceship,
+    }
+}
+
+/// names of the still lifes, oscillators and spaceships of the library by
+/// their apgcodes
+fn names() -> HashMap<String, String> {
+    let rule = Rule::default();
+    (library::catalog().iter())
+        .filter(|info| {
+            let objects = [
+                Category::StillLife,
+                Category::Oscillator,
+                Category::Spaceship,
+            ];
+            objects.contains(&info.category)
+        })
+        .filter_map(|info| {
+            let (kind, phases) = classify(&library::find(&info.name)?.cells, &rule)?;
+            Some((apgcode::encode(Some(kind), &phases), info.name.clone()))
+        })
+        .collect()
+}
+
+/// census of the objects live `cells` at `generation` of `rule` make
+pub fn take(cells: &[CellCoord], rule: &Rule, gap: u32, generation: u64) -> Census {
+    let names = match *rule == Rule::default() {
+        true => names(),
+        false => HashMap::new(),
+    };
+    let mut counts: HashMap<String, (Option<Category>, u64)> = HashMap::new();
+    for island in islands(cells, gap) {
+        let (apgcode, category) = match classify(&island, rule) {
+            Some((kind, phases)) => (apgcode::encode(Some(kind), &phases), Some(category(kind))),
+            None => (PATHOLOGICAL.to_string(), None),
+        };
+        counts.entry(apgcode).or_insert((category, 0)).1 += 1;
+    }
+    let mut objects: Vec<Tally> = (counts.into_iter())
+        .map(|(apgcode, (category, count))| Tally {
+            name: names.get(&apgcode).cloned(),
+            apgcode,
+            category,
+            count,
+        })
+        .collect();
+    objects.sort_by(|a, b| (b.count.cmp(&a.count)).then_with(|| a.apgcode.cmp(&b.apgcode)));
+    Census {
+        generation,
+        objects,
+    }
+}
+
+#[cfg(test)]
+mod test_census {
+    use super::*;
+
+    #[test]
+    fn test_islands() {
+        let cells = [[0, 0], [1, 1], [0, 3], [5, 5]];
+        let mut sizes: Vec<usize> = islands(&cells, 0).iter().map(Vec::len).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [1, 1, 2]);
+        let mut sizes: Vec<usize> = islands(&cells, 1).iter().map(Vec::len).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [1, 3]);
+        assert_eq!(islands(&cells, 3).len(), 1);
+    }
+
+    #[test]
+    /// blocks, blinkers and gliders apart from each other, and a pair of
+    /// blocks too close to count on their own
+    fn test_take() {
+        let block = |[row, col]: CellCoord| {
+            Life::new(&[
+                [row, col],
+                [row, col + 1],
+                [row + 1, col],
+                [row + 1, col + 1],
+            ])
+        };
+        let mut glider = Life::glider();
+        glider.translate(&[0, 20]);
+        let life = Life::blinker() | glider | block([20, 0]) | block([20, 20]);
+        let life = life | block([40, 0]) | block([40, 3]);
+        let census = take(&life.state_as_list(), &Rule::default(), 1, 7);
+        assert_eq!(census.generation, 7);
+        assert_eq!(census.to_string(), "2×Block, 1×Blinker, 1×Glider, 1×xs8_rr");
+        assert_eq!(census.objects[0].apgcode, "xs4_33");
+        assert_eq!(census.objects[0].category, Some(Category::StillLife));
+        let census = take(&life.state_as_list(), &Rule::default(), 0, 0);
+        assert_eq!(census.to_string(), "4×Block, 1×Blinker, 1×Glider");
+
+        // nothing is named in other rules, and the r-pentomino is far from
+        // settled
+        let highlife = "B36/S23".parse().unwrap();
+        let census = take(&Life::blinker().state_as_list(), &highlife, 1, 0);
+        assert_eq!(census.to_string(), "1×xp2_7");
+        let rpentomino = &library::find("R-pentomino").unwrap().cells;
+        let census = take(rpentomino, &Rule::default(), 1, 0);
+        assert_eq!(census.to_string(), "1×PATHOLOGICAL");
+    }
+}
@@ -0,0 +1,246 @@
+//! Finding the generation a run settles into a cycle, its period and how far
+//! it moves each period
+//!
+//! Each world has a [Fingerprint]: its population, the sums of the rows and
+//! columns of its cells and a hash adding up `g(state) * A^row * B^column`
+//! over them, all updated cell by cell as the world changes. A world moved by
+//! `[dr, dc]` has the same population, sums grown by `population * dr` and
+//! `population * dc` and a hash multiplied by `A^dr * B^dc`, so a repeat is
+//! told from the fingerprints alone.
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use super::{CellCoord, CellMap, Rule};
+
+const A: u64 = 0x2545_f491_4f6c_dd1d;
+const B: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// generations remembered, the longest period found is a bit shorter
+const MAX_SEEN: usize = 1 << 12;
+
+/// `base` to the power of `exp` modulo 2^64, `base` must be odd when `exp` is
+/// negative
+fn power(base: u64, exp: i64) -> u64 {
+    let base = match exp < 0 {
+        // Newton's iteration doubles the correct low bits of the inverse
+        true => (0..5).fold(base, |inverse, _| {
+            inverse.wrapping_mul(2_u64.wrapping_sub(base.wrapping_mul(inverse)))
+        }),
+        false => base,
+    };
+    let (mut result, mut square, mut exp) = (1_u64, base, exp.unsigned_abs());
+    while exp != 0 {
+        if exp & 1 != 0 {
+            result = result.wrapping_mul(square);
+        }
+        square = square.wrapping_mul(square);
+        exp >>= 1;
+    }
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Fingerprint {
+    hash: u64,
+    population: u64,
+    rows: i64,
+    cols: i64,
+}
+
+impl Fingerprint {
+    pub fn of(cells: &CellMap) -> Self {
+        let mut fingerprint = Self::default();
+        for (cell, state) in cells {
+            fingerprint.update(*cell, 0, *state);
+        }
+        fingerprint
+    }
+    /// `cell` went from the `old` state to the `new` one
+    pub fn update(&mut self, [row, col]: CellCoord, old: u8, new: u8) {
+        let term = |state: u8| {
+            u64::from(state)
+                .wrapping_mul(B)
+                .wrapping_mul(power(A, row.into()))
+                .wrapping_mul(power(B, col.into()))
+        };
+        for (state, sign) in [(old, -1), (new, 1)] {
+            if state == 0 {
+                continue;
+            }
+            self.hash = match sign {
+                1 => self.hash.wrapping_add(term(state)),
+                _ => self.hash.wrapping_sub(term(state)),
+            };
+            self.population = self.population.wrapping_add_signed(sign);
+            self.rows += sign * i64::from(row);
+            self.cols += sign * i64::from(col);
+        }
+    }
+    /// how far `earlier` moved to become this world, none when it did not
+    fn displacement(&self, earlier: &Self) -> Option<CellCoord> {
+        if self.population != earlier.population {
+            return None;
+        }
+        let population = self.population.max(1) as i64;
+        let moved = |now: i64, then: i64| -> Option<i32> {
+            let delta = now - then;
+            (delta % population == 0).then(|| i32::try_from(delta / population).ok())?
+        };
+        let [dr, dc] = [
+            moved(self.rows, earlier.rows)?,
+            moved(self.cols, earlier.cols)?,
+        ];
+        let hash = (earlier.hash)
+            .wrapping_mul(power(A, dr.into()))
+            .wrapping_mul(power(B, dc.into()));
+        (hash == self.hash).then_some([dr, dc])
+    }
+}
+
+/// A run that repeats itself
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    /// first generation seen again later, the run has settled by then
+    pub generation: u64,
+    /// generations between repeats, a multiple of the period when the run
+    /// jumped over generations
+    pub period: u64,
+    /// [rows, columns] moved each period, zero for oscillators
+    pub displacement: CellCoord,
+}
+
+/// Fingerprints of the generations of a run, until it repeats
+#[derive(Default)]
+pub struct Cycles {
+    /// fingerprints with a population, oldest first
+    seen: HashMap<u64, Vec<(u64, Fingerprint)>>,
+    /// generations in `seen` with their populations, oldest first
+    order: VecDeque<(u64, u64)>,
+    /// rule and last generation seen, anything else starts a new run
+    last: Option<(Rule, u64, Fingerprint)>,
+    found: Option<Cycle>,
+}
+
+impl Cycles {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+    /// the cycle the run settled into, while the world of `rule` at
+    /// `generation` is still the last one seen
+    pub fn found(&self, rule: &Rule, generation: u64, fingerprint: Fingerprint) -> Option<Cycle> {
+        let current = matches!(&self.last, Some((last_rule, last, seen))
+            if last_rule == rule && *last == generation && *seen == fingerprint);
+        current.then_some(self.found).flatten()
+    }
+    /// the world at `generation` has `fingerprint`. A world that does not
+    /// follow the last one seen with the same `rule`, such as an edited one,
+    /// starts a new run.
+    pub fn observe(&mut self, rule: &Rule, generation: u64, fingerprint: Fingerprint) {
+        match &self.last {
+            Some((last_rule, last, seen)) if last_rule == rule && *last == generation => {
+                if *seen == fingerprint {
+                    return;
+                }
+                self.reset();
+            }
+            Some((last_rule, last, _)) if last_rule != rule || *last > generation => self.reset(),
+            _ => (),
+        }
+        self.last = Some((rule.clone(), generation, fingerprint));
+        if self.found.is_some() {
+            return;
+        }
+        let population = fingerprint.population;
+        let earlier = self.seen.get(&population).into_iter().flatten();
+        self.found = earlier.rev().find_map(|(then, seen)| {
+            Some(Cycle {
+                generation: *then,
+                period: generation - then,
+                displacement: fingerprint.displacement(seen)?,
+            })
+        });
+        self.seen
+            .entry(population)
+            .or_default()
+            .push((generation, fingerprint));
+        self.order.push_back((generation, population));
+        if self.order.len() > MAX_SEEN {
+            let (_, population) = self.order.pop_front().unwrap();
+            let seen = self.seen.get_mut(&population).unwrap();
+            seen.remove(0);
+            if seen.is_empty() {
+                self.seen.remove(&population);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cycle {
+    use super::*;
+    use crate::app::Life;
+
+    #[test]
+    fn test_fingerprints() {
+        assert_eq!(power(A, -3).wrapping_mul(power(A, 3)), 1);
+        let glider = Life::glider();
+        let mut moved = glider.clone();
+        moved.translate(&[5, -7]);
+        let (start, end) = (
+            Fingerprint::of(&glider.state),
+            Fingerprint::of(&moved.state),
+        );
+        assert_eq!(end.displacement(&start), Some([5, -7]));
+        let mut flipped = glider.clone();
+        flipped.flip_cols();
+        assert_eq!(Fingerprint::of(&flipped.state).displacement(&start), None);
+
+        let mut edited = start;
+        edited.update([0, 0], 1, 0);
+        edited.update([9, 9], 0, 2);
+        let mut cells = glider.state.clone();
+        cells.remove(&[0, 0]);
+        cells.insert([9, 9], 2);
+        assert_eq!(edited, Fingerprint::of(&cells));
+    }
+
+    #[test]
+    /// the glider moves one cell up and right every 4 generations, a blinker
+    /// and a block settle at once
+    fn test_cycles() {
+        let rule = Rule::default();
+        for (life, period, displacement) in [
+            (Life::glider(), 4, [-1, 1]),
+            (Life::blinker(), 2, [0, 0]),
+            (Life::tub(), 1, [0, 0]),
+        ] {
+            let mut life = life;
+            let mut cycles = Cycles::default();
+            for generation in 0..10 {
+                cycles.observe(&rule, generation, Fingerprint::of(&life.state));
+                life.tick(&rule);
+            }
+            let last = Fingerprint::of(&life.state);
+            cycles.observe(&rule, 10, last);
+            let cycle = Cycle {
+                generation: 0,
+                period,
+                displacement,
+            };
+            assert_eq!(cycles.found(&rule, 10, last), Some(cycle));
+            assert_eq!(cycles.found(&rule, 9, last), None);
+        }
+
+        let mut cycles = Cycles::default();
+        let tub = Fingerprint::of(&Life::tub().state);
+        cycles.observe(&rule, 0, tub);
+        let highlife = "B36/S23".parse().unwrap();
+        cycles.observe(&highlife, 1, tub);
+        assert_eq!(cycles.found(&highlife, 1, tub), None);
+        // an edit at the generation last seen starts over
+        cycles.observe(&rule, 1, Fingerprint::default());
+        cycles.observe(&rule, 2, tub);
+        assert_eq!(cycles.found(&rule, 2, tub), None);
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    apgcode,
+    rle::{self, Comments, RleError},
+    table::Tables,
+    CellVector, Life, Rule, StateVector, ALIVE,
+};
+
+/// most live cells an imported world may have
+pub const MAX_CELLS: usize = 1 << 20;
+
+/// File formats the world can be exported to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FileFormat {
+    /// array of [row, column] pairs, or [row, column, state] for multi-state worlds
+    #[default]
+    Json,
+    /// run length encoded, readable by Golly
+    Rle,
+    /// canonical apgcode of the live cells, such as `xq4_153`
+    Apgcode,
+}
+
+impl FileFormat {
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Json => "json",
+            FileFormat::Rle => "rle",
+            FileFormat::Apgcode => "txt",
+        }
+    }
+}
+
+/// World read from a file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    pub cells: CellVector,
+    /// state of each cell, empty when every cell is alive
+    pub states: Vec<u8>,
+    /// rule declared by the file
+    pub rule: Option<Rule>,
+    pub comments: Comments,
+}
+
+impl Pattern {
+    /// cells paired with their states
+    pub fn cell_states(&self) -> StateVector {
+        let states = self.states.iter().copied().chain(std::iter::repeat(ALIVE));
+        self.cells.iter().copied().zip(states).collect()
+    }
+}
+
+/// Reasons an imported world is rejected
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// malformed contents of a recognized format
+    Syntax(String),
+    /// neither json, rle nor an apgcode
+    UnsupportedFormat,
+    /// more than [MAX_CELLS] live cells
+    TooManyCells(usize),
+    /// a cell outside the i32 coordinate range
+    CoordinateOverflow,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Syntax(msg) => write!(f, "{msg}"),
+            LoadError::UnsupportedFormat => write!(f, "expected a json, rle or apgcode file"),
+            LoadError::TooManyCells(n) => {
+                write!(f, "{n} cells is more than the limit of {MAX_CELLS}")
+            }
+            LoadError::CoordinateOverflow => write!(f, "cell coordinates are too large"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<RleError> for LoadError {
+    fn from(err: RleError) -> Self {
+        LoadError::Syntax(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> Self {
+        LoadError::Syntax(format!("invalid json world, {err}"))
+    }
+}
+
+fn decode_json(data: &[u8]) -> Result<Pattern, LoadError> {
+    // wide coordinates so out of range cells are told apart from bad syntax
+    let coords: Vec<Vec<i64>> = serde_json::from_slice(data)?;
+    if coords.len() > MAX_CELLS {
+        return Err(LoadError::TooManyCells(coords.len()));
+    }
+    let mut pattern = Pattern::default();
+    for coord in coords {
+        let (row, col, state) = match coord[..] {
+            [row, col] => (row, col, ALIVE.into()),
+            [row, col, state] => (row, col, state),
+            _ => return Err(LoadError::Syntax(format!("invalid json cell {coord:?}"))),
+        };
+        let (Ok(row), Ok(col)) = (row.try_into(), col.try_into()) else {
+            return Err(LoadError::CoordinateOverflow);
+        };
+        let state = match u8::try_from(state) {
+            Ok(state) if state != 0 => state,
+            _ => {
+                return Err(LoadError::Syntax(format!(
+                    "invalid json cell state {state}"
+                )))
+            }
+        };
+        pattern.cells.push([row, col]);
+        pattern.states.push(state);
+    }
+    if pattern.states.iter().all(|state| *state == ALIVE) {
+        pattern.states.clear();
+    }
+    Ok(pattern)
+}
+
+/// reads a json, rle or apgcode world, telling them apart by how they start
+pub fn decode(data: &[u8], tables: &Tables) -> Result<Pattern, LoadError> {
+    let text = std::str::from_utf8(data).map_err(|_| LoadError::UnsupportedFormat)?;
+    match text.trim_start().chars().next() {
+        Some('[') => decode_json(data),
+        Some(_) if apgcode::recognizes(text) => Ok(Pattern {
+            cells: apgcode::decode(text)?,
+            ..Pattern::default()
+        }),
+        Some('#' | 'x' | '0'..='9' | 'b' | 'o' | '$' | '!') => rle::parse(text, tables),
+        _ => Err(LoadError::UnsupportedFormat),
+    }
+}
+
+pub fn encode(
+    format: FileFormat,
+    cells: &StateVector,
+    rule: &Rule,
+    comments: &Comments,
+) -> Vec<u8> {
+    match format {
+        FileFormat::Json if cells.iter().all(|(_, state)| *state == ALIVE) => {
+            let coords: CellVector = cells.iter().map(|(cell, _)| *cell).collect();
+            serde_json::to_vec(&coords).unwrap()
+        }
+        FileFormat::Json => {
+            let coords: Vec<[i32; 3]> = cells
+                .iter()
+                .map(|([row, col], state)| [*row, *col, (*state).into()])
+                .collect();
+            serde_json::to_vec(&coords).unwrap()
+        }
+        FileFormat::Rle => rle::write(cells, rule, comments).into_bytes(),
+        // decaying cells have no place in apgcodes of two state rules
+        FileFormat::Apgcode => {
+            let live: CellVector = (cells.iter())
+                .filter(|(_, state)| *state == ALIVE)
+                .map(|(cell, _)| *cell)
+                .collect();
+            Life::new(&live).apgcode(rule).into_bytes()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_format {
+    use super::*;
+
+    /// decode knowing only the built in rule tables
+    fn decode(data: &[u8]) -> Result<Pattern, LoadError> {
+        super::decode(data, &Tables::default())
+    }
+
+    #[test]
+    fn test_decode() {
+        let json = decode(b" [[0, 1], [2, 3]]").unwrap();
+        assert_eq!(json.cells, vec![[0, 1], [2, 3]]);
+        assert_eq!(json.rule, None);
+
+        let rle = decode(b"x = 2, y = 1, rule = B36/S23\n2o!").unwrap();
+        assert_eq!(rle.cells, vec![[0, -1], [0, 0]]);
+        assert_eq!(rle.rule, "B36/S23".parse().ok());
+
+        let apgcode = decode(b"xq4_153\n").unwrap();
+        assert_eq!(apgcode.cells.len(), 5);
+        assert_eq!(apgcode.rule, None);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            decode(b"[[0, 1], [2]]"),
+            Err(LoadError::Syntax(_))
+        ));
+        assert!(matches!(decode(b"[[0, 1, 0]]"), Err(LoadError::Syntax(_))));
+        assert!(matches!(
+            decode(b"[[0, 1, 256]]"),
+            Err(LoadError::Syntax(_))
+        ));
+        assert_eq!(decode(b"<svg/>"), Err(LoadError::UnsupportedFormat));
+        assert_eq!(decode(b""), Err(LoadError::UnsupportedFormat));
+        assert_eq!(decode(&[0xff, 0xfe]), Err(LoadError::UnsupportedFormat));
+        assert_eq!(
+            decode(b"[[0, 4294967296]]"),
+            Err(LoadError::CoordinateOverflow)
+        );
+        let many = serde_json::to_vec(&vec![[0, 0]; MAX_CELLS + 1]).unwrap();
+        assert_eq!(decode(&many), Err(LoadError::TooManyCells(MAX_CELLS + 1)));
+    }
+
+    #[test]
+    fn test_encode() {
+        let cells = vec![([0, 0], ALIVE), ([0, 1], ALIVE)];
+        let rule = Rule::default();
+        let comments = Comments::default();
+        for format in [FileFormat::Json, FileFormat::Rle] {
+            let data = encode(format, &cells, &rule, &comments);
+            let pattern = decode(&data).unwrap();
+            assert_eq!(pattern.cell_states(), cells, "{format:?}");
+            assert!(pattern.states.is_empty(), "{format:?}");
+        }
+        assert_eq!(
+            encode(FileFormat::Json, &cells, &rule, &comments),
+            b"[[0,0],[0,1]]"
+        );
+        // a domino dies out, a block stays
+        assert_eq!(
+            encode(FileFormat::Apgcode, &cells, &rule, &comments),
+            b"zz_3"
+        );
+        let block = [[0, 0], [0, 1], [1, 0], [1, 1]].map(|cell| (cell, ALIVE));
+        let data = encode(FileFormat::Apgcode, &block.to_vec(), &rule, &comments);
+        assert_eq!(data, b"xs4_33");
+    }
+
+    #[test]
+    /// decaying cells keep their states
+    fn test_multi_state() {
+        let cells = vec![([0, 0], ALIVE), ([0, 1], 2), ([1, 3], 3)];
+        let rule: Rule = "B2/S/C4".parse().unwrap();
+        let comments = Comments::default();
+        for format in [FileFormat::Json, FileFormat::Rle] {
+            let data = encode(format, &cells, &rule, &comments);
+            let pattern = decode(&data).unwrap();
+            assert_eq!(pattern.cell_states(), cells, "{format:?}");
+        }
+        assert_eq!(
+            encode(FileFormat::Json, &cells, &rule, &comments),
+            b"[[0,0,1],[0,1,2],[1,3,3]]"
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::HashMap;
+
+use super::{CellCoord, CellVector, Rule, Simulation};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// the cache is dropped when it holds more nodes than this, even in the
+/// middle of a jump
+const NODE_LIMIT: usize = 1 << 22;
+/// largest supported jump, keeps moving patterns within i32 coordinates
+pub const MAX_STEP_POW: u32 = 30;
+
+/// children of a macro-cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Quad {
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    quad: Quad,
+    /// the node covers 2^level x 2^level cells
+    level: u32,
+    population: u64,
+}
+
+/// Quadtree engine with hash-consed macro-cells and memoized successors
+///
+/// The root of level `k` covers rows and columns in `-2^(k-1)..2^(k-1)`.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<Quad, NodeId>,
+    /// successor of (node, log2 of the step)
+    results: HashMap<(NodeId, u32), NodeId>,
+    /// empty node of each level
+    empties: Vec<NodeId>,
+    root: NodeId,
+    /// rule the memoized results were computed with
+    rule: Rule,
+    /// nodes kept before the cache is dropped
+    node_limit: usize,
+}
+
+impl Default for HashLife {
+    fn default() -> Self {
+        let leaf = |population| Node {
+            quad: Quad {
+                nw: DEAD,
+                ne: DEAD,
+                sw: DEAD,
+                se: DEAD,
+            },
+            level: 0,
+            population,
+        };
+        let mut hashlife = Self {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empties: vec![DEAD],
+            root: DEAD,
+            rule: Rule::default(),
+            node_limit: NODE_LIMIT,
+        };
+        hashlife.root = hashlife.empty(3);
+        hashlife
+    }
+}
+
+impl HashLife {
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+    fn level(&self, id: NodeId) -> u32 {
+        self.node(id).level
+    }
+    pub fn population(&self) -> u64 {
+        self.node(self.root).population
+    }
+
+    /// the unique node with these children
+    fn join(&mut self, quad: Quad) -> NodeId {
+        if let Some(&id) = self.index.get(&quad) {
+            return id;
+        }
+        let Quad { nw, ne, sw, se } = quad;
+        let population = [nw, ne, sw, se]
+            .iter()
+            .map(|&child| self.node(child).population)
+            .sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            quad,
+            level: self.level(nw) + 1,
+            population,
+        });
+        self.index.insert(quad, id);
+        id
+    }
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empties.len() <= level as usize {
+            let child = *self.empties.last().unwrap();
+            let empty = self.join(Quad {
+                nw: child,
+                ne: child,
+                sw: child,
+                se: child,
+            });
+            self.empties.push(empty);
+        }
+        self.empties[level as usize]
+    }
+    /// the centered node one level down
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let Quad { nw, ne, sw, se } = self.node(id).quad;
+        self.join(Quad {
+            nw: self.node(nw).quad.se,
+            ne: self.node(ne).quad.sw,
+            sw: self.node(sw).quad.ne,
+            se: self.node(se).quad.nw,
+        })
+    }
+    /// same cells, surrounded by a border of empty space
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let Quad { nw, ne, sw, se } = self.node(id).quad;
+        let e = self.empty(self.level(id) - 1);
+        let nw = self.join(Quad {
+            nw: e,
+            ne: e,
+            sw: e,
+            se: nw,
+        });
+        let ne = self.join(Quad {
+            nw: e,
+            ne: e,
+            sw: ne,
+            se: e,
+        });
+        let sw = self.join(Quad {
+            nw: e,
+            ne: sw,
+            sw: e,
+            se: e,
+        });
+        let se = self.join(Quad {
+            nw: se,
+            ne: e,
+            sw: e,
+            se: e,
+        });
+        self.join(Quad { nw, ne, sw, se })
+    }
+    /// all cells within the central quarter of the node
+    fn is_padded(&mut self, id: NodeId) -> bool {
+        let centre = self.centre(id);
+        let inner = self.centre(centre);
+        self.node(inner).population == self.node(id).population
+    }
+
+    /// one generation of the centre 2x2 of a 4x4 node
+    fn base(&mut self, id: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        let Quad { nw, ne, sw, se } = self.node(id).quad;
+        for (child, row, col) in [(nw, 0, 0), (ne, 0, 2), (sw, 2, 0), (se, 2, 2)] {
+            let Quad { nw, ne, sw, se } = self.node(child).quad;
+            grid[row][col] = nw == ALIVE;
+            grid[row][col + 1] = ne == ALIVE;
+            grid[row + 1][col] = sw == ALIVE;
+            grid[row + 1][col + 1] = se == ALIVE;
+        }
+        let next = |row: usize, col: usize| -> NodeId {
+            let neighbours = (self.rule.adjacency().offsets().iter().enumerate())
+                .filter(|(_, [dr, dc])| {
+                    grid[(row as i32 + dr) as usize][(col as i32 + dc) as usize]
+                })
+                .fold(0, |neighbours, (i, _)| neighbours | 1 << i);
+            let alive = if grid[row][col] {
+                self.rule.survive(neighbours)
+            } else {
+                self.rule.birth(neighbours)
+            };
+            if alive {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+        let quad = Quad {
+            nw: next(1, 1),
+            ne: next(1, 2),
+            sw: next(2, 1),
+            se: next(2, 2),
+        };
+        self.join(quad)
+    }
+
+    /// centre of the node, 2^pow generations ahead, requires pow + 2 <= level.
+    /// None once the cache outgrows its limit, unless only single generations
+    /// are left to step.
+    fn successor(&mut self, id: NodeId, pow: u32) -> Option<NodeId> {
+        let Node {
+            quad,
+            level,
+            population,
+        } = *self.node(id);
+        if population == 0 {
+            return Some(self.empty(level - 1));
+        }
+        if let Some(&result) = self.results.get(&(id, pow)) {
+            return Some(result);
+        }
+        if pow > 0 && self.nodes.len() > self.node_limit {
+            return None;
+        }
+        let result = if level == 2 {
+            self.base(id)
+        } else {
+            let Quad { nw, ne, sw, se } = quad;
+            let [a, b, c, d] = [nw, ne, sw, se].map(|child| self.node(child).quad);
+            let n01 = self.join(Quad {
+                nw: a.ne,
+                ne: b.nw,
+                sw: a.se,
+                se: b.sw,
+            });
+            let n10 = self.join(Quad {
+                nw: a.sw,
+                ne: a.se,
+                sw: c.nw,
+                se: c.ne,
+            });
+            let n11 = self.join(Quad {
+                nw: a.se,
+                ne: b.sw,
+                sw: c.ne,
+                se: d.nw,
+            });
+            let n12 = self.join(Quad {
+                nw: b.sw,
+                ne: b.se,
+                sw: d.nw,
+                se: d.ne,
+            });
+            let n21 = self.join(Quad {
+                nw: c.ne,
+                ne: d.nw,
+                sw: c.se,
+                se: d.sw,
+            });
+            let full_speed = pow + 2 == level;
+            let mut stage = [DEAD; 9];
+            for (result, n) in stage
+                .iter_mut()
+                .zip([nw, n01, ne, n10, n11, n12, sw, n21, se])
+            {
+                *result = match full_speed {
+                    true => self.successor(n, pow - 1)?,
+                    false => self.centre(n),
+                };
+            }
+            let [r00, r01, r02, r10, r11, r12, r20, r21, r22] = stage;
+            let stage_pow = if full_speed { pow - 1 } else { pow };
+            let quads = [
+                [r00, r01, r10, r11],
+                [r01, r02, r11, r12],
+                [r10, r11, r20, r21],
+                [r11, r12, r21, r22],
+            ];
+            let mut corners = [DEAD; 4];
+            for (corner, [nw, ne, sw, se]) in corners.iter_mut().zip(quads) {
+                let quad = self.join(Quad { nw, ne, sw, se });
+                *corner = self.successor(quad, stage_pow)?;
+            }
+            let [nw, ne, sw, se] = corners;
+            self.join(Quad { nw, ne, sw, se })
+        };
+        self.results.insert((id, pow), result);
+        Some(result)
+    }
+
+    /// node of `level` with its top left corner at `origin`
+    fn build(&mut self, level: u32, origin: [i64; 2], cells: Vec<[i64; 2]>) -> NodeId {
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+        let half = 1 << (level - 1);
+        let [row, col] = origin;
+        let (top, bottom): (Vec<_>, Vec<_>) = cells.into_iter().partition(|c| c[0] < row + half);
+        let (nw, ne): (Vec<_>, Vec<_>) = top.into_iter().partition(|c| c[1] < col + half);
+        let (sw, se): (Vec<_>, Vec<_>) = bottom.into_iter().partition(|c| c[1] < col + half);
+        let quad = Quad {
+            nw: self.build(level - 1, [row, col], nw),
+            ne: self.build(level - 1, [row, col + half], ne),
+            sw: self.build(level - 1, [row + half, col], sw),
+            se: self.build(level - 1, [row + half, col + half], se),
+        };
+        self.join(quad)
+    }
+
+    fn collect(&self, id: NodeId, origin: [i64; 2], cells: &mut Vec<[i64; 2]>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push(origin);
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        let [row, col] = origin;
+        let Quad { nw, ne, sw, se } = node.quad;
+        self.collect(nw, [row, col], cells);
+        self.collect(ne, [row, col + half], cells);
+        self.collect(sw, [row + half, col], cells);
+        self.collect(se, [row + half, col + half], cells);
+    }
+
+    /// all live cells of the node with its top left corner at `origin` lie
+    /// within i32 coordinates
+    fn fits(&self, id: NodeId, origin: [i64; 2]) -> bool {
+        let node = self.node(id);
+        let end = origin.map(|start| start + (1 << node.level) - 1);
+        let within = |coord: &i64| i32::try_from(*coord).is_ok();
+        if node.population == 0 || origin.iter().chain(&end).all(within) {
+            return true;
+        }
+        if node.level == 0 {
+            return false;
+        }
+        let half = 1 << (node.level - 1);
+        let [row, col] = origin;
+        let Quad { nw, ne, sw, se } = node.quad;
+        self.fits(nw, [row, col])
+            && self.fits(ne, [row, col + half])
+            && self.fits(sw, [row + half, col])
+            && self.fits(se, [row + half, col + half])
+    }
+    /// top left corner of the root
+    fn origin(&self) -> [i64; 2] {
+        let half = 1 << (self.level(self.root) - 1);
+        [-half, -half]
+    }
+
+    /// drop the cache, building the world again from its cells
+    fn rebuild(&mut self) {
+        let (level, origin) = (self.level(self.root), self.origin());
+        let mut cells = Vec::new();
+        self.collect(self.root, origin, &mut cells);
+        self.clear_cache();
+        self.root = self.build(level, origin, cells);
+    }
+
+    fn clear_cache(&mut self) {
+        self.nodes.truncate(2);
+        self.index.clear();
+        self.results.clear();
+        self.empties.truncate(1);
+    }
+}
+
+impl Simulation for HashLife {
+    fn tick(&mut self, rule: &Rule) {
+        self.step_pow(rule, 0);
+    }
+
+    fn step_pow(&mut self, rule: &Rule, pow: u32) {
+        let pow = pow.min(MAX_STEP_POW);
+        // a world stepped again and again is not loaded in between to drop
+        // the cache
+        if self.nodes.len() > self.node_limit {
+            self.rebuild();
+        }
+        if *rule != self.rule {
+            self.results.clear();
+            self.rule = rule.clone();
+        }
+        let mut root = self.root;
+        while self.level(root) < pow + 3 || !self.is_padded(root) {
+            root = self.expand(root);
+        }
+        match self.successor(root, pow) {
+            Some(next) => self.root = next,
+            // the cache filled up, the jump is taken in two halves
+            None => {
+                self.rebuild();
+                self.step_pow(rule, pow - 1);
+                self.step_pow(rule, pow - 1);
+            }
+        }
+    }
+
+    fn load(&mut self, cells: &[CellCoord]) {
+        if self.nodes.len() > self.node_limit {
+            self.clear_cache();
+        }
+        let radius = cells
+            .iter()
+            .flatten()
+            .map(|&x| (x as i64).abs() + 1)
+            .max()
+            .unwrap_or(0);
+        let mut level = 3;
+        while 1 << (level - 1) < radius {
+            level += 1;
+        }
+        let half = 1 << (level - 1);
+        let cells = cells.iter().map(|c| [c[0] as i64, c[1] as i64]).collect();
+        self.root = self.build(level, [-half, -half], cells);
+    }
+
+    fn cells(&self) -> CellVector {
+        let mut cells = Vec::with_capacity(self.population() as usize);
+        self.collect(self.root, self.origin(), &mut cells);
+        (cells.into_iter())
+            .filter_map(|[row, col]| Some([i32::try_from(row).ok()?, i32::try_from(col).ok()?]))
+            .collect()
+    }
+
+    fn overflows(&self) -> bool {
+        !self.fits(self.root, self.origin())
+    }
+}
+
+#[cfg(test)]
+mod test_hashlife {
+    use super::super::Life;
+    use super::*;
+
+    fn sorted(mut cells: CellVector) -> CellVector {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    /// hashlife agrees with the naive engine on a chaotic pattern
+    fn test_r_pentomino() {
+        let rule = Rule::default();
+        let mut life = Life::new(&[[0, 0], [0, 1], [1, -1], [1, 0], [2, 0]]);
+        let mut hashlife = HashLife::default();
+        hashlife.load(&life.cells());
+        for pow in [0, 0, 1, 3, 5] {
+            life.step_pow(&rule, pow);
+            hashlife.step_pow(&rule, pow);
+            assert_eq!(sorted(hashlife.cells()), sorted(life.cells()));
+        }
+        for rule in ["B36/S23", "B2n3/S23-q", "B2ik3/S12-a3"] {
+            let rule: Rule = rule.parse().unwrap();
+            life.step_pow(&rule, 4);
+            hashlife.step_pow(&rule, 4);
+            assert_eq!(sorted(hashlife.cells()), sorted(life.cells()), "{rule}");
+        }
+    }
+
+    #[test]
+    /// a glider travels one cell diagonally every 4 generations
+    fn test_glider_jump() {
+        let rule = Rule::default();
+        let mut glider = Life::glider();
+        let mut hashlife = HashLife::default();
+        hashlife.load(&glider.cells());
+        hashlife.step_pow(&rule, 12);
+
+        let start = sorted(glider.cells());
+        glider.step_pow(&rule, 2);
+        let end = sorted(glider.cells());
+        let delta = [end[0][0] - start[0][0], end[0][1] - start[0][1]];
+        glider.translate(&[delta[0] * 1023, delta[1] * 1023]);
+        assert_eq!(sorted(hashlife.cells()), sorted(glider.cells()));
+    }
+
+    #[test]
+    /// a cache filling up in the middle of a jump is dropped and the jump
+    /// taken in smaller steps
+    fn test_node_limit() {
+        let rule = Rule::default();
+        let mut life = Life::new(&[[0, 0], [0, 1], [1, -1], [1, 0], [2, 0]]);
+        let mut hashlife = HashLife {
+            node_limit: 1 << 10,
+            ..Default::default()
+        };
+        hashlife.load(&life.cells());
+        hashlife.step_pow(&rule, 9);
+        life.step_pow(&rule, 9);
+        assert_eq!(sorted(hashlife.cells()), sorted(life.cells()));
+    }
+
+    #[test]
+    /// gliders leaving i32 coordinates are reported, not wrapped around
+    fn test_overflow() {
+        let rule = Rule::default();
+        let mut glider = Life::glider();
+        glider.translate(&[i32::MIN + 20, i32::MAX - 20]);
+        let mut hashlife = HashLife::default();
+        hashlife.load(&glider.cells());
+        hashlife.step_pow(&rule, 6);
+        assert!(!hashlife.overflows());
+        assert_eq!(hashlife.cells().len(), 5);
+        hashlife.step_pow(&rule, 7);
+        assert!(hashlife.overflows());
+        assert!(hashlife.cells().len() < 5);
+    }
+}
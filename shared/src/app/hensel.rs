@@ -0,0 +1,269 @@
+//! Hensel notation for isotropic non-totalistic rules, e.g. `B2n3/S23-q`
+//!
+//! Neighbourhoods with the same count of live neighbours are told apart by a
+//! letter shared by all their rotations and reflections: `2c` is two corners
+//! on the same side while `2n` is two opposite corners. The letters for 5 to 7
+//! neighbours name the complement of the same letter for 3 to 1.
+use super::{library::Rotation, CellCoord};
+
+/// Live neighbours of a cell, bit `i` is set when the neighbour at `OFFSETS[i]` is alive
+pub type Neighbourhood = u8;
+
+/// [row, column] of each neighbour relative to the cell, in bit order
+pub const OFFSETS: [CellCoord; 8] = [
+    [-1, -1],
+    [-1, 0],
+    [-1, 1],
+    [0, -1],
+    [0, 1],
+    [1, -1],
+    [1, 0],
+    [1, 1],
+];
+
+/// letters of each count of live neighbours, in canonical order
+pub const LETTERS: [&str; 9] = [
+    "",
+    "ce",
+    "ceaikn",
+    "ceaiknjqry",
+    "ceaiknjqrytwz",
+    "ceaiknjqry",
+    "ceaikn",
+    "ce",
+    "",
+];
+
+/// a neighbourhood for each letter of up to 4 neighbours as Golly writes them,
+/// bit `3 * row + column` of the 3x3 block around the cell
+const GOLLY_NEIGHBOURHOODS: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// count and letter of every neighbourhood
+const CLASSES: [(u8, u8); 256] = classes();
+
+const fn index(cell: CellCoord) -> usize {
+    let mut i = 0;
+    while OFFSETS[i][0] != cell[0] || OFFSETS[i][1] != cell[1] {
+        i += 1;
+    }
+    i
+}
+
+/// `neighbourhood` turned a quarter clockwise, or mirrored left to right
+const fn transform(neighbourhood: Neighbourhood, mirror: bool) -> Neighbourhood {
+    let mut moved = 0;
+    let mut i = 0;
+    while i < 8 {
+        if neighbourhood & 1 << i != 0 {
+            let [row, col] = OFFSETS[i];
+            let cell = match mirror {
+                true => [row, -col],
+                false => Rotation::R90.rotate([row, col]),
+            };
+            moved |= 1 << index(cell);
+        }
+        i += 1;
+    }
+    moved
+}
+
+const fn from_golly(block: u16) -> Neighbourhood {
+    let mut neighbourhood = 0;
+    let mut i = 0;
+    while i < 8 {
+        let [row, col] = OFFSETS[i];
+        if block & 1 << (3 * (row + 1) + col + 1) != 0 {
+            neighbourhood |= 1 << i;
+        }
+        i += 1;
+    }
+    neighbourhood
+}
+
+const fn classes() -> [(u8, u8); 256] {
+    let mut classes = [(0, 0); 256];
+    let mut count = 0;
+    while count < GOLLY_NEIGHBOURHOODS.len() {
+        let blocks = GOLLY_NEIGHBOURHOODS[count];
+        let mut letter = 0;
+        while letter < blocks.len() {
+            // four turns, then four turns of the mirror image
+            let mut neighbourhood = from_golly(blocks[letter]);
+            let mut turn = 0;
+            while turn < 8 {
+                let class = (count as u8, letter as u8);
+                classes[neighbourhood as usize] = class;
+                if count < 4 {
+                    classes[!neighbourhood as usize] = (8 - class.0, class.1);
+                }
+                neighbourhood = transform(neighbourhood, turn == 3);
+                turn += 1;
+            }
+            letter += 1;
+        }
+        count += 1;
+    }
+    classes
+}
+
+/// count of live neighbours and the index of its letter in [LETTERS]
+pub const fn class(neighbourhood: Neighbourhood) -> (u8, u8) {
+    CLASSES[neighbourhood as usize]
+}
+
+/// Set of neighbourhoods, bit `letter` of entry `count` for each class in it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Transitions([u16; 9]);
+
+impl Transitions {
+    /// every neighbourhood with a count of live neighbours in the `counts` bitmask
+    pub const fn totalistic(counts: u16) -> Self {
+        let mut letters = [0; 9];
+        let mut count = 0;
+        while count < 9 {
+            if counts & 1 << count != 0 {
+                letters[count] = Self::all(count as u8);
+            }
+            count += 1;
+        }
+        Self(letters)
+    }
+    /// letters of `count` live neighbours
+    pub const fn all(count: u8) -> u16 {
+        match LETTERS[count as usize].len() {
+            0 => 1,
+            n => (1 << n) - 1,
+        }
+    }
+    pub const fn contains(&self, neighbourhood: Neighbourhood) -> bool {
+        let (count, letter) = class(neighbourhood);
+        self.0[count as usize] & 1 << letter != 0
+    }
+    /// bitmask of the letters of `count` in the set
+    pub const fn letters(&self, count: u8) -> u16 {
+        self.0[count as usize]
+    }
+    pub fn set_letters(&mut self, count: u8, letters: u16) {
+        self.0[count as usize] = letters;
+    }
+}
+
+#[cfg(test)]
+mod test_hensel {
+    use super::*;
+
+    /// 3x3 picture around the cell, `o` for live neighbours
+    fn neighbourhood(picture: &str) -> Neighbourhood {
+        let cells: Vec<char> = picture.chars().filter(|c| !c.is_whitespace()).collect();
+        OFFSETS.iter().enumerate().fold(0, |n, (i, [row, col])| {
+            match cells[(3 * (row + 1) + col + 1) as usize] {
+                'o' => n | 1 << i,
+                _ => n,
+            }
+        })
+    }
+
+    fn name(neighbourhood: Neighbourhood) -> String {
+        let (count, letter) = class(neighbourhood);
+        let letter = LETTERS[count as usize].chars().nth(letter.into());
+        format!("{count}{}", letter.map_or(String::new(), String::from))
+    }
+
+    #[test]
+    /// one neighbourhood of each class, turned or mirrored from the way Golly
+    /// lists it
+    fn test_reference_table() {
+        let table = [
+            ("... .x. ...", "0"),
+            ("..o .x. ...", "1c"),
+            ("... .x. .o.", "1e"),
+            ("... .x. o.o", "2c"),
+            ("... ox. .o.", "2e"),
+            (".oo .x. ...", "2a"),
+            (".o. .x. .o.", "2i"),
+            ("... .xo o..", "2k"),
+            ("..o .x. o..", "2n"),
+            ("o.. .x. o.o", "3c"),
+            (".o. oxo ...", "3e"),
+            ("oo. ox. ...", "3a"),
+            ("... .x. ooo", "3i"),
+            (".o. .xo o..", "3k"),
+            ("o.o ox. ...", "3n"),
+            (".o. .xo ..o", "3j"),
+            ("..o .x. oo.", "3q"),
+            (".o. .x. oo.", "3r"),
+            ("..o ox. ..o", "3y"),
+            ("o.o .x. o.o", "4c"),
+            (".o. oxo .o.", "4e"),
+            ("... ox. ooo", "4a"),
+            (".oo .x. .oo", "4i"),
+            ("oo. .xo o..", "4k"),
+            ("o.o .xo ..o", "4n"),
+            ("..o oxo .o.", "4j"),
+            ("oo. ox. ..o", "4q"),
+            (".oo oxo ...", "4r"),
+            ("o.o .x. oo.", "4y"),
+            ("o.. oxo o..", "4t"),
+            ("..o .xo oo.", "4w"),
+            ("..o oxo o..", "4z"),
+            (".o. oxo .oo", "5c"),
+            ("ooo .x. o.o", "5e"),
+            ("ooo ox. o..", "5a"),
+            ("... oxo ooo", "5i"),
+            (".oo ox. o.o", "5k"),
+            ("ooo ox. .o.", "5n"),
+            (".oo .xo o.o", "5j"),
+            ("..o oxo oo.", "5q"),
+            ("..o oxo o.o", "5r"),
+            (".oo ox. .oo", "5y"),
+            ("ooo oxo .o.", "6c"),
+            ("ooo ox. o.o", "6e"),
+            ("..o oxo ooo", "6a"),
+            ("o.o oxo o.o", "6i"),
+            ("ooo .xo oo.", "6k"),
+            (".oo oxo oo.", "6n"),
+            ("ooo oxo oo.", "7c"),
+            ("o.o oxo ooo", "7e"),
+            ("ooo oxo ooo", "8"),
+        ];
+        for (picture, expected) in table {
+            assert_eq!(name(neighbourhood(picture)), expected, "{picture}");
+        }
+    }
+
+    #[test]
+    /// every letter names one class of neighbourhoods, of the sizes Hensel lists
+    fn test_classes() {
+        let mut sizes: Vec<Vec<usize>> = LETTERS
+            .iter()
+            .map(|letters| vec![0; letters.len().max(1)])
+            .collect();
+        for neighbourhood in 0..=255 {
+            let (count, letter) = class(neighbourhood);
+            assert_eq!(count, neighbourhood.count_ones() as u8);
+            sizes[count as usize][letter as usize] += 1;
+        }
+        assert_eq!(
+            sizes,
+            vec![
+                vec![1],
+                vec![4, 4],
+                vec![4, 4, 8, 2, 8, 2],
+                vec![4, 4, 4, 4, 4, 8, 8, 8, 8, 4],
+                vec![1, 1, 8, 4, 8, 8, 8, 4, 8, 8, 4, 4, 4],
+                vec![4, 4, 4, 4, 4, 8, 8, 8, 8, 4],
+                vec![4, 4, 8, 2, 8, 2],
+                vec![4, 4],
+                vec![1],
+            ]
+        );
+        let conway = Transitions::totalistic(1 << 2 | 1 << 3);
+        assert!((0..=255).all(|n| conway.contains(n) == matches!(n.count_ones(), 2 | 3)));
+    }
+}
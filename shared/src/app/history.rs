@@ -0,0 +1,243 @@
+//! Undo and redo of edits, loads and steps
+//!
+//! Edits are kept as the states of the cells they changed, loads as the world they replaced
+//! and runs of steps as checkpoints taken every [History::CHECKPOINT_INTERVAL]
+//! generations, so any generation of the run can be rebuilt by replaying from
+//! the checkpoint before it.
+use std::{collections::VecDeque, mem::size_of};
+
+use super::{
+    random::{Chances, Random},
+    CellCoord, Rule, StateVector,
+};
+
+/// Whole world at some generation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub cells: StateVector,
+    pub rule: Rule,
+    pub generation: u64,
+    /// random numbers a stochastic rule goes on with
+    pub random: Random,
+    pub chances: Chances,
+    /// seed `random` started from
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    /// cells changed by an edit with the states they had before, swapping
+    /// them back reverts it
+    Edit(StateVector),
+    /// world before a load
+    Replace(Snapshot),
+    /// checkpoints of consecutive steps, the first is the world before them
+    Run(Vec<Snapshot>),
+}
+
+impl Snapshot {
+    fn bytes(&self) -> usize {
+        size_of::<Snapshot>() + self.cells.len() * size_of::<(CellCoord, u8)>()
+    }
+}
+
+impl Entry {
+    fn bytes(&self) -> usize {
+        size_of::<Entry>()
+            + match self {
+                Entry::Edit(cells) => cells.len() * size_of::<(CellCoord, u8)>(),
+                Entry::Replace(snapshot) => snapshot.bytes(),
+                Entry::Run(checkpoints) => checkpoints.iter().map(Snapshot::bytes).sum(),
+            }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    /// oldest first
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+    /// approximate memory held by both stacks
+    bytes: usize,
+}
+
+impl History {
+    /// memory kept before the oldest entries are forgotten
+    pub const MAX_BYTES: usize = 64 << 20;
+    /// generations between the checkpoints of a run
+    pub const CHECKPOINT_INTERVAL: u64 = 64;
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    /// the last entry is a run that has gone past `generation`
+    pub fn can_step_back(&self, generation: u64) -> bool {
+        matches!(self.undo.back(), Some(Entry::Run(checkpoints)) if checkpoints[0].generation < generation)
+    }
+
+    /// record a new change, forgetting what could be redone
+    pub fn push(&mut self, entry: Entry) {
+        self.bytes -= self.redo.drain(..).map(|e| e.bytes()).sum::<usize>();
+        self.push_undo(entry);
+    }
+    /// record a change that was redone
+    pub fn push_undo(&mut self, entry: Entry) {
+        self.bytes += entry.bytes();
+        self.undo.push_back(entry);
+        self.trim();
+    }
+    pub fn push_redo(&mut self, entry: Entry) {
+        self.bytes += entry.bytes();
+        self.redo.push(entry);
+        self.trim();
+    }
+    pub fn undo(&mut self) -> Option<Entry> {
+        let entry = self.undo.pop_back()?;
+        self.bytes -= entry.bytes();
+        Some(entry)
+    }
+    pub fn redo(&mut self) -> Option<Entry> {
+        let entry = self.redo.pop()?;
+        self.bytes -= entry.bytes();
+        Some(entry)
+    }
+
+    /// a step from `generation` needs a checkpoint of the world before it
+    pub fn checkpoint_due(&self, generation: u64) -> bool {
+        match self.undo.back() {
+            Some(Entry::Run(checkpoints)) => {
+                let last = checkpoints.last().map_or(0, |s| s.generation);
+                generation >= last + Self::CHECKPOINT_INTERVAL
+            }
+            _ => true,
+        }
+    }
+    /// continue the current run with `snapshot`, or start a new one
+    pub fn checkpoint(&mut self, snapshot: Snapshot) {
+        match self.undo.back_mut() {
+            Some(Entry::Run(checkpoints)) => {
+                self.bytes += snapshot.bytes();
+                checkpoints.push(snapshot);
+                self.bytes -= self.redo.drain(..).map(|e| e.bytes()).sum::<usize>();
+                self.trim();
+            }
+            _ => self.push(Entry::Run(vec![snapshot])),
+        }
+    }
+    /// latest checkpoint before `generation`, dropping the ones after it.
+    /// The world is rebuilt by replaying from it to `generation - 1`.
+    pub fn step_back(&mut self, generation: u64) -> Option<Snapshot> {
+        if !self.can_step_back(generation) {
+            return None;
+        }
+        let target = generation - 1;
+        let Some(Entry::Run(checkpoints)) = self.undo.back_mut() else {
+            return None;
+        };
+        while matches!(checkpoints.last(), Some(s) if s.generation > target) {
+            self.bytes -= checkpoints.pop().unwrap().bytes();
+        }
+        if checkpoints.len() == 1 && checkpoints[0].generation == target {
+            let Some(Entry::Run(mut checkpoints)) = self.undo() else {
+                unreachable!()
+            };
+            return checkpoints.pop();
+        }
+        checkpoints.last().cloned()
+    }
+
+    /// forget the oldest entries, then the middle checkpoints of the last run,
+    /// until the history fits in [History::MAX_BYTES]
+    fn trim(&mut self) {
+        while self.bytes > Self::MAX_BYTES && self.undo.len() > 1 {
+            let entry = self.undo.pop_front().unwrap();
+            self.bytes -= entry.bytes();
+        }
+        while self.bytes > Self::MAX_BYTES {
+            match self.undo.back_mut() {
+                Some(Entry::Run(checkpoints)) if checkpoints.len() > 1 => {
+                    self.bytes -= checkpoints.remove(1).bytes();
+                }
+                _ => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::*;
+
+    fn snapshot(generation: u64, cells: usize) -> Snapshot {
+        Snapshot {
+            cells: vec![([0, 0], 1); cells],
+            rule: Rule::default(),
+            generation,
+            random: Random::default(),
+            chances: Chances::default(),
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        history.push(Entry::Edit(vec![([0, 0], 0)]));
+        history.push(Entry::Edit(vec![([1, 1], 0)]));
+        let entry = history.undo().unwrap();
+        assert_eq!(entry, Entry::Edit(vec![([1, 1], 0)]));
+        history.push_redo(entry);
+        assert!(history.can_redo());
+        // a new change forgets the redo stack
+        history.push(Entry::Edit(vec![([2, 2], 0)]));
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(), Some(Entry::Edit(vec![([2, 2], 0)])));
+        assert_eq!(history.undo(), Some(Entry::Edit(vec![([0, 0], 0)])));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.bytes, 0);
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let mut history = History::default();
+        for generation in 0..200 {
+            if history.checkpoint_due(generation) {
+                history.checkpoint(snapshot(generation, 1));
+            }
+        }
+        let Some(Entry::Run(checkpoints)) = history.undo.back() else {
+            panic!("expected a run");
+        };
+        let generations: Vec<_> = checkpoints.iter().map(|s| s.generation).collect();
+        assert_eq!(generations, vec![0, 64, 128, 192]);
+
+        assert_eq!(history.step_back(200).unwrap().generation, 192);
+        assert_eq!(history.step_back(193).unwrap().generation, 192);
+        assert_eq!(history.step_back(192).unwrap().generation, 128);
+        assert_eq!(history.step_back(1).unwrap().generation, 0);
+        assert!(!history.can_undo());
+        assert_eq!(history.step_back(1), None);
+        assert_eq!(history.bytes, 0);
+    }
+
+    #[test]
+    fn test_memory_cap() {
+        let big = History::MAX_BYTES / size_of::<(CellCoord, u8)>() / 4;
+        let mut history = History::default();
+        history.push(Entry::Edit(vec![([0, 0], 0)]));
+        for generation in 0..8 {
+            history.checkpoint(snapshot(generation * 64, big));
+        }
+        assert!(history.bytes <= History::MAX_BYTES);
+        // the edit is forgotten, the start of the run is kept
+        assert_eq!(history.undo.len(), 1);
+        let Some(Entry::Run(checkpoints)) = history.undo.back() else {
+            panic!("expected a run");
+        };
+        assert_eq!(checkpoints[0].generation, 0);
+        assert_eq!(checkpoints.last().unwrap().generation, 7 * 64);
+    }
+}
@@ -0,0 +1,288 @@
+//! Named patterns embedded in the core, stamped into the world with
+//! [Event::PlacePattern](super::Event::PlacePattern)
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use super::{format::Pattern, rle, table::Tables, CellCoord, StateVector};
+
+/// Kind of pattern, for grouping the palette
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// unchanging
+    StillLife,
+    /// returns to its starting state
+    Oscillator,
+    /// moves across the grid
+    Spaceship,
+    /// emits spaceships forever
+    Gun,
+    /// moves and leaves debris behind
+    Puffer,
+    /// small pattern that takes long to settle
+    Methuselah,
+}
+
+/// Catalog entry shown by the shells
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PatternInfo {
+    pub name: String,
+    pub category: Category,
+}
+
+/// Clockwise rotation applied when placing a pattern
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+impl Rotation {
+    /// `cell` turned around the origin, rows growing downwards
+    pub const fn rotate(&self, [row, col]: CellCoord) -> CellCoord {
+        match self {
+            Rotation::R0 => [row, col],
+            Rotation::R90 => [col, -row],
+            Rotation::R180 => [-row, -col],
+            Rotation::R270 => [-col, row],
+        }
+    }
+}
+
+const LIBRARY: &[(&str, Category, &str)] = &[
+    (
+        "Block",
+        Category::StillLife,
+        include_str!("../../patterns/block.rle"),
+    ),
+    (
+        "Beehive",
+        Category::StillLife,
+        include_str!("../../patterns/beehive.rle"),
+    ),
+    (
+        "Loaf",
+        Category::StillLife,
+        include_str!("../../patterns/loaf.rle"),
+    ),
+    (
+        "Boat",
+        Category::StillLife,
+        include_str!("../../patterns/boat.rle"),
+    ),
+    (
+        "Tub",
+        Category::StillLife,
+        include_str!("../../patterns/tub.rle"),
+    ),
+    (
+        "Blinker",
+        Category::Oscillator,
+        include_str!("../../patterns/blinker.rle"),
+    ),
+    (
+        "Toad",
+        Category::Oscillator,
+        include_str!("../../patterns/toad.rle"),
+    ),
+    (
+        "Beacon",
+        Category::Oscillator,
+        include_str!("../../patterns/beacon.rle"),
+    ),
+    (
+        "Pulsar",
+        Category::Oscillator,
+        include_str!("../../patterns/pulsar.rle"),
+    ),
+    (
+        "Pentadecathlon",
+        Category::Oscillator,
+        include_str!("../../patterns/pentadecathlon.rle"),
+    ),
+    (
+        "Glider",
+        Category::Spaceship,
+        include_str!("../../patterns/glider.rle"),
+    ),
+    (
+        "LWSS",
+        Category::Spaceship,
+        include_str!("../../patterns/lwss.rle"),
+    ),
+    (
+        "MWSS",
+        Category::Spaceship,
+        include_str!("../../patterns/mwss.rle"),
+    ),
+    (
+        "HWSS",
+        Category::Spaceship,
+        include_str!("../../patterns/hwss.rle"),
+    ),
+    (
+        "Gosper glider gun",
+        Category::Gun,
+        include_str!("../../patterns/gosperglidergun.rle"),
+    ),
+    (
+        "Puffer train",
+        Category::Puffer,
+        include_str!("../../patterns/puffertrain.rle"),
+    ),
+    (
+        "R-pentomino",
+        Category::Methuselah,
+        include_str!("../../patterns/rpentomino.rle"),
+    ),
+    (
+        "Diehard",
+        Category::Methuselah,
+        include_str!("../../patterns/diehard.rle"),
+    ),
+    (
+        "Acorn",
+        Category::Methuselah,
+        include_str!("../../patterns/acorn.rle"),
+    ),
+];
+
+lazy_static! {
+    /// the library parsed once, in catalog order
+    static ref PATTERNS: Vec<(PatternInfo, Pattern)> = LIBRARY
+        .iter()
+        .map(|(name, category, text)| {
+            let info = PatternInfo {
+                name: name.to_string(),
+                category: *category,
+            };
+            let pattern = rle::parse(text, &Tables::default());
+            (info, pattern.expect("library patterns are valid rle"))
+        })
+        .collect();
+    static ref CATALOG: Vec<PatternInfo> = PATTERNS.iter().map(|(info, _)| info.clone()).collect();
+}
+
+/// patterns that can be placed with [super::Event::PlacePattern]. They never
+/// change, so shells read them once rather than with every view.
+pub fn catalog() -> &'static [PatternInfo] {
+    &CATALOG
+}
+
+/// pattern called `name`, centered on the origin
+pub fn find(name: &str) -> Option<&'static Pattern> {
+    let (_, pattern) = PATTERNS.iter().find(|(info, _)| info.name == name)?;
+    Some(pattern)
+}
+
+/// cells of `pattern` mirrored, rotated and then moved to `at`, with their states
+pub fn orient(pattern: &Pattern, at: CellCoord, rotation: Rotation, mirror: bool) -> StateVector {
+    pattern
+        .cell_states()
+        .into_iter()
+        .map(|([row, col], state)| (if mirror { [row, -col] } else { [row, col] }, state))
+        .map(|(cell, state)| (rotation.rotate(cell), state))
+        .map(|([row, col], state)| ([row + at[0], col + at[1]], state))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_library {
+    use super::*;
+    use crate::app::{Life, Rule};
+
+    fn life(name: &str) -> Life {
+        Life::new(&find(name).unwrap().cells)
+    }
+
+    fn period(name: &str) -> Option<usize> {
+        let start = life(name);
+        let mut life = start.clone();
+        (1..=30).find(|_| {
+            life.tick(&Rule::default());
+            life.state == start.state
+        })
+    }
+
+    #[test]
+    fn test_catalog() {
+        for info in catalog() {
+            let pattern = find(&info.name).unwrap();
+            assert!(!pattern.cells.is_empty(), "{}", info.name);
+            assert_eq!(pattern.rule, Some(Rule::default()), "{}", info.name);
+            if info.category == Category::StillLife {
+                assert_eq!(period(&info.name), Some(1), "{}", info.name);
+            }
+        }
+        assert_eq!(
+            find("Glider").unwrap().comments.name.as_deref(),
+            Some("Glider")
+        );
+        assert_eq!(find("glider"), None);
+        // parsed once and shared by every view
+        assert!(std::ptr::eq(catalog(), catalog()));
+        assert!(std::ptr::eq(
+            find("Glider").unwrap(),
+            find("Glider").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_periods() {
+        assert_eq!(period("Blinker"), Some(2));
+        assert_eq!(period("Toad"), Some(2));
+        assert_eq!(period("Beacon"), Some(2));
+        assert_eq!(period("Pulsar"), Some(3));
+        assert_eq!(period("Pentadecathlon"), Some(15));
+        for ship in ["Glider", "LWSS", "MWSS", "HWSS"] {
+            assert_eq!(period(ship), None, "{ship}");
+        }
+    }
+
+    #[test]
+    fn test_long_lived() {
+        let mut diehard = life("Diehard");
+        for _ in 0..129 {
+            diehard.tick(&Rule::default());
+        }
+        assert!(!diehard.state.is_empty());
+        diehard.tick(&Rule::default());
+        assert!(diehard.state.is_empty());
+
+        let mut gun = life("Gosper glider gun");
+        let mut puffer = life("Puffer train");
+        let (gun_start, puffer_start) = (gun.state.len(), puffer.state.len());
+        for _ in 0..300 {
+            gun.tick(&Rule::default());
+            puffer.tick(&Rule::default());
+        }
+        assert!(gun.state.len() >= gun_start + 5 * 9);
+        assert!(puffer.state.len() > 2 * puffer_start);
+    }
+
+    #[test]
+    fn test_orient() {
+        let glider = find("Glider").unwrap();
+        let mut turned: Vec<_> = orient(glider, [10, 20], Rotation::R90, true)
+            .into_iter()
+            .map(|(cell, _)| cell)
+            .collect();
+        turned.sort();
+        insta::assert_ron_snapshot!(turned, @r#"
+        [
+          (9, 19),
+          (9, 20),
+          (10, 19),
+          (10, 21),
+          (11, 19),
+        ]
+        "#);
+        for rotation in [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270] {
+            let full_turn = (0..4).fold([2, 5], |cell, _| rotation.rotate(cell));
+            assert_eq!(full_turn, [2, 5]);
+        }
+        assert_eq!(Rotation::R90.rotate([0, 1]), [1, 0]);
+    }
+}
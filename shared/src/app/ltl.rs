@@ -0,0 +1,436 @@
+//! Larger than Life rules, e.g. Bosco's rule `R5,C0,M1,S34..58,B34..45,NM`
+//!
+//! Cells count the live cells within `range` of them, in a square for `NM` or
+//! a diamond for `NN`, and themselves too with `M1`. A cell is born or
+//! survives when its count is in one of the `B` or `S` intervals, listed like
+//! `S2,3,5-6`, and `C3` or more states make cells decay like in Generations
+//! rules.
+//!
+//! Counts are read from summed-area tables of [TILE] sized tiles padded by the
+//! range, so reading one takes a few lookups whatever the range, but filling
+//! a table looks at each of its `(TILE + 2 * range)^2` cells.
+use std::{collections::HashSet, fmt::Display};
+
+use super::{CellCoord, CellMap, Rule, ALIVE};
+
+/// side of the tiles the world is counted in
+const TILE: i32 = 64;
+/// largest range Golly supports
+pub const MAX_RANGE: u32 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// square of side `2 * range + 1`, `NM`
+    Moore,
+    /// cells at most `range` rows plus columns away, `NN`
+    VonNeumann,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeRule {
+    range: u32,
+    /// the cell counts itself
+    middle: bool,
+    /// counts a live cell survives with
+    survive: Counts,
+    /// counts a dead cell is born with
+    birth: Counts,
+    shape: Shape,
+}
+
+/// Sorted inclusive intervals of counts, apart from each other
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+struct Counts(Vec<[u32; 2]>);
+
+impl Counts {
+    fn contains(&self, count: u32) -> bool {
+        (self.0.iter()).any(|[min, max]| (*min..=*max).contains(&count))
+    }
+    /// add the interval `[min, max]`, joining it with those it touches
+    fn insert(&mut self, [mut min, mut max]: [u32; 2]) {
+        self.0.retain(|&[start, end]| {
+            let apart = end + 1 < min || max + 1 < start;
+            if !apart {
+                (min, max) = (min.min(start), max.max(end));
+            }
+            apart
+        });
+        let at = self.0.partition_point(|[start, _]| *start < min);
+        self.0.insert(at, [min, max]);
+    }
+    fn min(&self) -> u32 {
+        self.0.first().map_or(0, |[min, _]| *min)
+    }
+    fn max(&self) -> u32 {
+        self.0.last().map_or(0, |[_, max]| *max)
+    }
+}
+
+/// intervals like `34..58,60`
+impl Display for Counts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let intervals: Vec<String> = (self.0.iter())
+            .map(|[min, max]| format!("{min}..{max}"))
+            .collect();
+        write!(f, "{}", intervals.join(","))
+    }
+}
+
+/// interval like `34..58`, `34-58` or a single count like `3`
+fn parse_interval(text: &str) -> Option<[u32; 2]> {
+    let (min, max) = text
+        .split_once("..")
+        .or_else(|| text.split_once('-'))
+        .unwrap_or((text, text));
+    match [min.parse().ok()?, max.parse().ok()?] {
+        [min, max] if min <= max => Some([min, max]),
+        _ => None,
+    }
+}
+
+impl RangeRule {
+    /// the rule and state count of `R5,C0,M1,S34..58,B34..45,NM`, where `C`,
+    /// `M` and `N` may be left out for `C0`, `M0` and `NM`, and `S` and `B`
+    /// may list more intervals like `S2,3,5-6`
+    pub fn parse(rulestring: &str) -> Option<(Self, u8)> {
+        let (mut range, mut states, mut middle) = (None, 2, false);
+        let (mut survive, mut birth, mut shape) = (None, None, Shape::Moore);
+        let mut key = None;
+        for field in rulestring.split(',').map(str::trim) {
+            // more intervals of the `S` or `B` field before
+            if field.starts_with(|c: char| c.is_ascii_digit()) {
+                let counts: &mut Option<Counts> = match key? {
+                    'S' => &mut survive,
+                    'B' => &mut birth,
+                    _ => return None,
+                };
+                counts.as_mut()?.insert(parse_interval(field)?);
+                continue;
+            }
+            let mut chars = field.chars();
+            key = Some(chars.next()?.to_ascii_uppercase());
+            let value = chars.as_str();
+            match key? {
+                'R' => range = Some(value.parse().ok()?),
+                'C' => {
+                    states = match value.parse().ok()? {
+                        0 | 2 => 2,
+                        n if n > 2 => n,
+                        _ => return None,
+                    }
+                }
+                'M' => {
+                    middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return None,
+                    }
+                }
+                'S' => survive = Some(Counts(vec![parse_interval(value)?])),
+                'B' => birth = Some(Counts(vec![parse_interval(value)?])),
+                'N' => {
+                    shape = match value.to_ascii_uppercase().as_str() {
+                        "M" => Shape::Moore,
+                        "N" => Shape::VonNeumann,
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            }
+        }
+        let rule = Self {
+            range: range.filter(|r| (1..=MAX_RANGE).contains(r))?,
+            middle,
+            survive: survive?,
+            birth: birth?,
+            shape,
+        };
+        // births with no live neighbours would fill the plane
+        let valid =
+            rule.birth.min() > 0 && rule.survive.max().max(rule.birth.max()) <= rule.max_count();
+        valid.then_some((rule, states))
+    }
+
+    pub fn write(&self, f: &mut std::fmt::Formatter<'_>, states: u8) -> std::fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{},B{},N{}",
+            self.range,
+            if states > 2 { states } else { 0 },
+            u8::from(self.middle),
+            self.survive,
+            self.birth,
+            match self.shape {
+                Shape::Moore => 'M',
+                Shape::VonNeumann => 'N',
+            }
+        )
+    }
+
+    /// cells in the neighbourhood, the middle one included
+    pub const fn max_count(&self) -> u32 {
+        let range = self.range;
+        match self.shape {
+            Shape::Moore => (2 * range + 1) * (2 * range + 1),
+            Shape::VonNeumann => 2 * range * (range + 1) + 1,
+        }
+    }
+
+    /// live cells around `cell`, itself included
+    fn count(&self, sums: &Sums, [row, col]: CellCoord) -> u32 {
+        let range = self.range as i32;
+        match self.shape {
+            Shape::Moore => sums.rect([row - range, col - range], [row + range, col + range]),
+            Shape::VonNeumann => (-range..=range)
+                .map(|dr| {
+                    let width = range - dr.abs();
+                    sums.rect([row + dr, col - width], [row + dr, col + width])
+                })
+                .sum(),
+        }
+    }
+
+    /// the generation after `cells`, on the topology of `rule`
+    pub fn tick(&self, cells: &CellMap, rule: &Rule) -> CellMap {
+        let topology = rule.topology();
+        let range = self.range as i32;
+        let alive = |cell: CellCoord| {
+            topology
+                .wrap(cell)
+                .map_or(false, |cell| cells.get(&cell) == Some(&ALIVE))
+        };
+        // decaying cells age wherever they are
+        let mut next: CellMap = cells
+            .iter()
+            .filter(|(_, state)| **state > ALIVE)
+            .map(|(cell, state)| (*cell, rule.decay(*state)))
+            .filter(|(_, state)| *state != 0)
+            .collect();
+        // every tile within range of a live cell
+        let tile = |n: i32| n.div_euclid(TILE);
+        let mut tiles = HashSet::new();
+        for ([row, col], _) in cells.iter().filter(|(_, state)| **state == ALIVE) {
+            for tile_row in tile(row - range)..=tile(row + range) {
+                for tile_col in tile(col - range)..=tile(col + range) {
+                    tiles.insert([tile_row, tile_col]);
+                }
+            }
+        }
+        let mut sums = Sums::default();
+        for [tile_row, tile_col] in tiles {
+            let [top, left] = [tile_row * TILE, tile_col * TILE];
+            sums.fill([top - range, left - range], TILE + 2 * range, alive);
+            for row in top..top + TILE {
+                for col in left..left + TILE {
+                    // cells past a wrapping edge stand for the cell they wrap to
+                    let Some(cell) = topology.wrap([row, col]) else {
+                        continue;
+                    };
+                    let state = cells.get(&cell).copied().unwrap_or(0);
+                    let count = self.count(&sums, [row, col]);
+                    let state = match state {
+                        ALIVE => {
+                            let count = if self.middle { count } else { count - 1 };
+                            match self.survive.contains(count) {
+                                true => ALIVE,
+                                false => rule.decay(ALIVE),
+                            }
+                        }
+                        0 if self.birth.contains(count) => ALIVE,
+                        _ => continue,
+                    };
+                    if state != 0 {
+                        next.insert(cell, state);
+                    }
+                }
+            }
+        }
+        next
+    }
+}
+
+/// Summed-area table of the live cells in a square block
+#[derive(Default)]
+struct Sums {
+    /// top left cell of the block
+    origin: CellCoord,
+    side: i32,
+    /// live cells above and left of each corner, `side + 1` squared
+    sums: Vec<u32>,
+}
+
+impl Sums {
+    fn fill(&mut self, origin: CellCoord, side: i32, alive: impl Fn(CellCoord) -> bool) {
+        let stride = side as usize + 1;
+        self.origin = origin;
+        self.side = side;
+        self.sums.clear();
+        self.sums.resize(stride * stride, 0);
+        for row in 0..side as usize {
+            let mut line = 0;
+            for col in 0..side as usize {
+                line += u32::from(alive([origin[0] + row as i32, origin[1] + col as i32]));
+                self.sums[(row + 1) * stride + col + 1] = self.sums[row * stride + col + 1] + line;
+            }
+        }
+    }
+    /// live cells in rows `top..=bottom` and columns `left..=right`, which
+    /// must lie inside the block
+    fn rect(&self, [top, left]: CellCoord, [bottom, right]: CellCoord) -> u32 {
+        let stride = self.side as usize + 1;
+        let index = |row: i32, col: i32| {
+            (row - self.origin[0]) as usize * stride + (col - self.origin[1]) as usize
+        };
+        self.sums[index(bottom + 1, right + 1)] + self.sums[index(top, left)]
+            - self.sums[index(top, right + 1)]
+            - self.sums[index(bottom + 1, left)]
+    }
+}
+
+#[cfg(test)]
+mod test_ltl {
+    use super::*;
+    use crate::app::Life;
+
+    /// cells of a pseudo random soup in a `side` square at `origin`
+    fn soup(origin: CellCoord, side: i32, seed: u64) -> Vec<CellCoord> {
+        let mut state = seed;
+        let mut cells = Vec::new();
+        for row in 0..side {
+            for col in 0..side {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                if state >> 62 == 0 {
+                    cells.push([origin[0] + row, origin[1] + col]);
+                }
+            }
+        }
+        cells
+    }
+
+    /// the next generation counted one neighbour at a time
+    fn brute_force(ltl: &RangeRule, cells: &CellMap, rule: &Rule) -> CellMap {
+        let topology = rule.topology();
+        let range = ltl.range as i32;
+        let alive = |cell: CellCoord| {
+            topology
+                .wrap(cell)
+                .map_or(false, |cell| cells.get(&cell) == Some(&ALIVE))
+        };
+        let mut candidates: HashSet<CellCoord> = cells.keys().copied().collect();
+        for [row, col] in cells.keys() {
+            for dr in -range..=range {
+                for dc in -range..=range {
+                    candidates.extend(topology.wrap([row + dr, col + dc]));
+                }
+            }
+        }
+        let mut next = CellMap::new();
+        for [row, col] in candidates {
+            let count = (-range..=range)
+                .flat_map(|dr| (-range..=range).map(move |dc| (dr, dc)))
+                .filter(|(dr, dc)| match ltl.shape {
+                    Shape::Moore => true,
+                    Shape::VonNeumann => dr.abs() + dc.abs() <= range,
+                })
+                .filter(|(dr, dc)| ltl.middle || (*dr, *dc) != (0, 0))
+                .filter(|(dr, dc)| alive([row + dr, col + dc]))
+                .count() as u32;
+            let state = match cells.get(&[row, col]).copied().unwrap_or(0) {
+                ALIVE if ltl.survive.contains(count) => ALIVE,
+                0 if ltl.birth.contains(count) => ALIVE,
+                0 => 0,
+                state => rule.decay(state),
+            };
+            if state != 0 {
+                next.insert([row, col], state);
+            }
+        }
+        next
+    }
+
+    #[test]
+    fn test_notation() {
+        let bosco = "R5,C0,M1,S34..58,B34..45,NM";
+        let (ltl, states) = RangeRule::parse(bosco).unwrap();
+        assert_eq!(states, 2);
+        assert_eq!(ltl.max_count(), 121);
+        assert_eq!(bosco.parse::<Rule>().unwrap().to_string(), bosco);
+        assert_eq!(
+            "r2,c3,s2-5,b3,nn:T100,80"
+                .parse::<Rule>()
+                .unwrap()
+                .to_string(),
+            "R2,C3,M0,S2..5,B3..3,NN:T100,80"
+        );
+        // comma lists join the intervals they touch
+        assert_eq!(
+            "R2,C0,M1,S5-6,2,3,B3,7..9,8,NM"
+                .parse::<Rule>()
+                .unwrap()
+                .to_string(),
+            "R2,C0,M1,S2..3,5..6,B3..3,7..9,NM"
+        );
+        for invalid in [
+            "R0,C0,M0,S1..2,B1..2,NM",
+            "R501,C0,M0,S1..2,B1..2,NM",
+            "R1,C1,M0,S1..2,B1..2,NM",
+            "R1,C0,M2,S1..2,B1..2,NM",
+            "R1,C0,M0,S2..1,B1..2,NM",
+            "R1,C0,M0,S1..2,B0..2,NM",
+            "R1,C0,M0,S1..10,B1..2,NM",
+            "R1,C0,M0,S1..2,NM",
+            "R1,C0,M0,S1..2,B1..2,NX",
+            "R1,3,C0,M0,S1..2,B1..2,NM",
+            "R1,C0,M0,S1..2,B1,10,NM",
+            "R1,C0,M0,S1..2,B1,0..2,NM",
+        ] {
+            assert!(RangeRule::parse(invalid).is_none(), "{invalid}");
+        }
+    }
+
+    #[test]
+    /// range 1 rules step like the matching B/S rules
+    fn test_range_one() {
+        let start = Life::new(&soup([-7, -3], 20, 1));
+        for (ltl, rule) in [
+            ("R1,C0,M0,S2..3,B3,NM", "B3/S23"),
+            ("R1,C0,M0,S2,3,B3,6,NM", "B36/S23"),
+            ("R1,C0,M1,S3..4,B3,NM", "B3/S23"),
+            ("R1,C0,M0,S1,B1,NN", "B1/S1V"),
+            ("R1,C3,M0,S3..4,B2,NM", "B2/S34/C3"),
+        ] {
+            let (mut life, mut expected) = (start.clone(), start.clone());
+            for _ in 0..20 {
+                life.tick(&ltl.parse().unwrap());
+                expected.tick(&rule.parse().unwrap());
+            }
+            assert_eq!(life.state, expected.state, "{ltl}");
+        }
+    }
+
+    #[test]
+    /// tiles count the same as a direct scan, across tile and world edges
+    fn test_against_brute_force() {
+        for rulestring in [
+            "R5,C0,M1,S34..58,B34..45,NM",
+            "R3,C4,M0,S6..14,B7..10,NN",
+            "R4,C0,M1,S20..40,B22..30,NM:T50,40",
+            "R4,C0,M1,S20..40,B22..30,NM:K50*,40",
+            "R2,C0,M0,S3..8,B5..7,NM:P30,30",
+        ] {
+            let rule: Rule = rulestring.parse().unwrap();
+            let ltl = rule.range().unwrap();
+            let mut life = Life::new(&soup([-10, -25], 30, 7));
+            life.fit(rule.topology());
+            let mut expected = life.state.clone();
+            for _ in 0..6 {
+                life.tick(&rule);
+                expected = brute_force(ltl, &expected, &rule);
+                assert_eq!(life.state, expected, "{rulestring}");
+            }
+            assert!(!expected.is_empty(), "{rulestring}");
+        }
+    }
+}
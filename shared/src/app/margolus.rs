@@ -0,0 +1,216 @@
+//! Block rules on the Margolus neighbourhood written as in MCell, e.g. the
+//! billiard ball model `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`
+//!
+//! The world is cut into 2x2 blocks, shifted one cell down and right on odd
+//! generations, and each block is replaced by the entry of the table for its
+//! cells read as a 4 bit number: top left is bit 0, then top right, bottom
+//! left and bottom right. The table must be a permutation, so every rule is
+//! reversible and runs backwards with the inverse table. Blocks crossing the
+//! edge of a bounded world stay as they are, so no cell is lost.
+use std::collections::HashSet;
+
+use super::{CellCoord, CellMap, Topology, ALIVE};
+
+/// most cells of a world a rule that fills empty blocks may run on
+pub const MAX_FILLED_CELLS: u64 = 1 << 22;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Margolus {
+    /// new contents of each block
+    table: [u8; 16],
+}
+
+impl Margolus {
+    /// `MS,D` and 16 entries separated by `;`
+    pub fn parse(rulestring: &str) -> Option<Self> {
+        let rulestring = rulestring.trim();
+        let prefix = rulestring.get(..4)?;
+        if !prefix.eq_ignore_ascii_case("MS,D") {
+            return None;
+        }
+        let mut table = [0; 16];
+        let mut entries = rulestring[4..].split(';').map(|n| n.trim().parse::<u8>());
+        for entry in table.iter_mut() {
+            *entry = entries.next()?.ok().filter(|n| *n < 16)?;
+        }
+        let mut seen = [false; 16];
+        table.iter().for_each(|n| seen[*n as usize] = true);
+        (entries.next().is_none() && !seen.contains(&false)).then_some(Self { table })
+    }
+
+    pub fn write(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> = self.table.iter().map(u8::to_string).collect();
+        write!(f, "MS,D{}", entries.join(";"))
+    }
+
+    /// the rule that undoes a step of this one
+    pub fn inverse(&self) -> Self {
+        let mut table = [0; 16];
+        for (block, new) in self.table.iter().enumerate() {
+            table[*new as usize] = block as u8;
+        }
+        Self { table }
+    }
+    /// empty blocks come alive
+    pub const fn fills_space(&self) -> bool {
+        self.table[0] != 0
+    }
+    /// the rule runs in `topology`: a plane, or a bounded world or a torus
+    /// with an even width and height so blocks line up with the edges, and a
+    /// finite one when empty blocks come alive
+    pub fn runs_in(&self, topology: Topology) -> bool {
+        let size = topology.size().map(|[height, width]| (height, width));
+        let shape = match topology {
+            Topology::Plane => true,
+            Topology::Bounded { width, height } | Topology::Torus { width, height } => {
+                width % 2 == 0 && height % 2 == 0
+            }
+            Topology::KleinBottle { .. } | Topology::CrossSurface { .. } => false,
+        };
+        let filled = match size {
+            Some((height, width)) => u64::from(height) * u64::from(width) <= MAX_FILLED_CELLS,
+            None => false,
+        };
+        shape && (filled || !self.fills_space())
+    }
+
+    /// the generation after `cells`, with the blocks shifted on `odd` ones
+    pub fn step(&self, cells: &CellMap, topology: Topology, odd: bool) -> CellMap {
+        let corners = topology.corners();
+        let [top, left] = corners.map_or([0, 0], |[top_left, _]| top_left);
+        let shift = i32::from(odd);
+        let align = |coord: i32, start: i32| coord - (coord - start - shift).rem_euclid(2);
+        // blocks crossing a wrapping edge are known by their top left cell
+        let corner = |[row, col]: CellCoord| {
+            let corner = [align(row, top), align(col, left)];
+            topology.wrap(corner).unwrap_or(corner)
+        };
+        let blocks: HashSet<CellCoord> = match corners {
+            Some([_, [bottom, right]]) if self.fills_space() => {
+                let rows = (align(top, top)..=bottom).step_by(2);
+                rows.flat_map(|row| {
+                    (align(left, left)..=right)
+                        .step_by(2)
+                        .map(move |col| corner([row, col]))
+                })
+                .collect()
+            }
+            _ => cells.keys().copied().map(corner).collect(),
+        };
+        let mut next = CellMap::new();
+        for [row, col] in blocks {
+            let block = [
+                [row, col],
+                [row, col + 1],
+                [row + 1, col],
+                [row + 1, col + 1],
+            ]
+            .map(|cell| topology.wrap(cell));
+            let index = (block.iter().enumerate())
+                .filter(|(_, cell)| matches!(cell, Some(cell) if cells.get(cell) == Some(&ALIVE)))
+                .fold(0, |index, (i, _)| index | 1 << i);
+            // a block half past a bounded edge would lose cells there
+            let new = match block.contains(&None) {
+                true => index as u8,
+                false => self.table[index],
+            };
+            next.extend(
+                (block.iter().enumerate())
+                    .filter(|(i, _)| new & 1 << i != 0)
+                    .filter_map(|(_, cell)| Some((cell.as_ref().copied()?, ALIVE))),
+            );
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod test_margolus {
+    use super::*;
+    use crate::app::{Life, Rule};
+
+    const BILLIARD_BALLS: &str = "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15";
+    const CRITTERS: &str = "MS,D15;1;2;12;4;10;9;7;8;6;5;11;3;13;14;0";
+
+    #[test]
+    fn test_notation() {
+        let rule: Rule = BILLIARD_BALLS.parse().unwrap();
+        assert_eq!(rule.to_string(), BILLIARD_BALLS);
+        let critters = format!("{CRITTERS}:T16,8");
+        assert_eq!(critters.parse::<Rule>().unwrap().to_string(), critters);
+        let margolus = Margolus::parse(CRITTERS).unwrap();
+        assert!(margolus.fills_space());
+        assert_eq!(margolus.inverse().inverse(), margolus);
+        for invalid in [
+            "MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14",
+            "MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;15;0",
+            "MS,D0;0;2;3;4;5;6;7;8;9;10;11;12;13;14;15",
+            "MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;16",
+            CRITTERS,
+            &format!("{CRITTERS}:T15,8"),
+            &format!("{CRITTERS}:K16,8"),
+            &format!("{BILLIARD_BALLS}:P16,9"),
+            &format!("{CRITTERS}:T4096,4096"),
+        ] {
+            assert!(invalid.parse::<Rule>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    /// a lone ball of the billiard ball model moves one cell diagonally each
+    /// generation, and two of them bounce off each other
+    fn test_billiard_balls() {
+        let rule: Rule = BILLIARD_BALLS.parse().unwrap();
+        let mut life = Life::new(&[[0, 0]]);
+        for _ in 0..3 {
+            life.tick(&rule);
+        }
+        assert_eq!(life.state_as_list(), [[3, 3]]);
+        life.tick_back(&rule);
+        assert_eq!(life.state_as_list(), [[2, 2]]);
+
+        // heading for the same block from its top right and bottom left
+        let mut life = Life::new(&[[0, 1], [1, 0]]);
+        life.tick(&rule);
+        let mut cells = life.state_as_list();
+        cells.sort();
+        assert_eq!(cells, [[0, 0], [1, 1]]);
+    }
+
+    #[test]
+    /// critters on a torus come back to the start when run backwards
+    fn test_reversible() {
+        let rule: Rule = format!("{CRITTERS}:T16,12").parse().unwrap();
+        let soup: Vec<CellCoord> = (0..40)
+            .map(|i: i32| [(i * 7) % 12 - 6, (i * 13) % 16 - 8])
+            .collect();
+        let mut life = Life::new(&soup);
+        let start = life.state.clone();
+        for _ in 0..25 {
+            life.tick(&rule);
+        }
+        assert_ne!(life.state, start);
+        for _ in 0..25 {
+            life.tick_back(&rule);
+        }
+        assert_eq!(life.state, start);
+    }
+
+    #[test]
+    /// balls of the billiard ball model stop at the edges of a bounded
+    /// world rather than leave it, and every step can be undone
+    fn test_bounded() {
+        let rule: Rule = format!("{BILLIARD_BALLS}:P8,6").parse().unwrap();
+        let mut life = Life::new(&[[-3, -4], [-2, 0], [1, 2], [2, 3]]);
+        let start = life.state.clone();
+        for _ in 0..20 {
+            life.tick(&rule);
+            assert_eq!(life.state.len(), start.len());
+        }
+        assert_ne!(life.state, start);
+        for _ in 0..20 {
+            life.tick_back(&rule);
+        }
+        assert_eq!(life.state, start);
+    }
+}
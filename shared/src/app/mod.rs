@@ -1,17 +1,60 @@
 use std::ops::BitOr;
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
 
 use cgmath::num_traits::Float;
 use cgmath::{Array, Vector2};
 use crux_core::{macros::Effect, render::Render};
 use serde::{Deserialize, Serialize};
 
+mod adjacency;
+mod apgcode;
 mod capabilities;
-pub use capabilities::ExportOperation;
-use capabilities::{Alert, FileIO};
+mod census;
+mod cycle;
+mod format;
+mod hashlife;
+mod hensel;
+mod history;
+mod library;
+mod ltl;
+mod margolus;
+mod random;
+mod rle;
+mod rule;
+mod selection;
+mod soup;
+mod table;
+mod tiles;
+mod topology;
+mod wolfram;
+pub use adjacency::Layout;
+use capabilities::{Alert, FileIO, Timer};
+pub use capabilities::{AlertOpereation, ExportOperation, TimerOperation};
+pub use census::{Census, Tally};
+pub use cycle::Cycle;
+use cycle::{Cycles, Fingerprint};
+pub use format::FileFormat;
+use hashlife::HashLife;
+use hensel::Neighbourhood;
+use history::{Entry, History, Snapshot};
+pub use library::{catalog, Category, PatternInfo, Rotation};
+pub use random::Chances;
+use random::Random;
+use rle::Comments;
+pub use rule::Rule;
+use rule::ALIVE;
+use selection::Rect;
+pub use soup::Symmetry;
+use table::Tables;
+use tiles::Tiles;
+pub use topology::Topology;
 #[allow(unused)]
 // use log::{debug, error, info, warn};
 use uniffi::deps::log::{debug, info};
+use wolfram::Wolfram;
 
 #[derive(Default)]
 pub struct App;
@@ -19,17 +62,56 @@ pub struct App;
 /// [row, column]
 type CellCoord = [i32; 2];
 
-type CellSet = HashSet<CellCoord>;
+/// state of each cell that is not dead, see [Rule]
+type CellMap = HashMap<CellCoord, u8>;
 
 type CellVector = Vec<CellCoord>;
+/// cells with their states
+type StateVector = Vec<(CellCoord, u8)>;
 pub type Vec2 = Vector2<f32>;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Life {
-    state: CellSet,
-    buffer: CellVector,
+    state: CellMap,
+    buffer: StateVector,
+    /// row the next generation of a one dimensional rule grows from, the
+    /// bottom live row when unset
+    #[serde(skip)]
+    row: Option<i32>,
+    /// block rules shift their blocks on odd generations
+    #[serde(skip)]
+    odd: bool,
+    /// cells that changed since the last generation of a B/S rule, unset
+    /// when every cell needs a look
+    #[serde(skip)]
+    active: Option<Active>,
+    /// fingerprint of `state` kept up to date cell by cell, unset when it
+    /// needs working out again
+    #[serde(skip)]
+    fingerprint: Option<Fingerprint>,
 }
 
+/// Cells that changed in the last generation of `rule` or were edited since,
+/// only those and their neighbours can change in the next one
+#[derive(Clone, Debug)]
+struct Active {
+    rule: Rule,
+    cells: HashSet<CellCoord>,
+}
+
+/// worlds are the same when their cells are, whatever is kept to step them
+impl PartialEq for Life {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl Eq for Life {}
+
+/// fewest cells stepped on several threads
+#[cfg(feature = "parallel")]
+const PARALLEL_CELLS: usize = 1 << 14;
+
 const INIT_LIFE: &[u8] = include_bytes!("../../init_life.json");
 
 impl Default for Life {
@@ -51,6 +133,10 @@ impl BitOr for Life {
         Self {
             state: self.state,
             buffer,
+            row: None,
+            odd: self.odd,
+            active: None,
+            fingerprint: None,
         }
     }
 }
@@ -82,34 +168,56 @@ impl Life {
 }
 
 impl Life {
-    fn translate(&mut self, delta: &CellCoord) {
+    /// move every cell with `f`, dropping those it maps to none
+    fn move_cells(&mut self, f: impl Fn(CellCoord) -> Option<CellCoord>) {
+        self.row = None;
+        self.active = None;
+        self.fingerprint = None;
         self.buffer.clear();
         self.buffer.extend(
             self.state
                 .drain()
-                .map(|cell| [cell[0] + delta[0], cell[1] + delta[1]]),
+                .filter_map(|(cell, state)| Some((f(cell)?, state))),
         );
         self.state.extend(self.buffer.drain(..));
     }
-    fn flip_rows(&mut self) {
-        self.buffer.clear();
-        self.buffer
-            .extend(self.state.drain().map(|cell| [-cell[0], cell[1]]));
-        self.state.extend(self.buffer.drain(..));
+    fn translate(&mut self, delta: &CellCoord) {
+        self.move_cells(|cell| Some([cell[0] + delta[0], cell[1] + delta[1]]));
+    }
+    #[cfg(test)]
+    fn flip_cols(&mut self) {
+        self.move_cells(|cell| Some([cell[0], -cell[1]]));
+    }
+    /// turn clockwise around the origin
+    #[cfg(test)]
+    fn rotate(&mut self, rotation: Rotation) {
+        self.move_cells(|cell| Some(rotation.rotate(cell)));
     }
     fn empty() -> Self {
         Self {
-            state: HashSet::new(),
+            state: HashMap::new(),
             buffer: Vec::new(),
+            row: None,
+            odd: false,
+            active: None,
+            fingerprint: Some(Fingerprint::default()),
         }
     }
     fn clear(&mut self) {
+        self.row = None;
+        self.active = None;
+        self.fingerprint = Some(Fingerprint::default());
         self.state.clear();
         self.buffer.clear();
     }
     fn add_cells(&mut self, spawns: &[CellCoord]) {
+        self.row = None;
         for cell in spawns {
-            self.state.insert(*cell);
+            let old = self.state.insert(*cell, ALIVE);
+            self.changed(*cell, old.unwrap_or(0), ALIVE);
+        }
+        if let Some(active) = &mut self.active {
+            active.cells.extend(spawns);
         }
     }
     fn new(init_life: &[CellCoord]) -> Self {
@@ -117,75 +225,399 @@ impl Life {
         game.add_cells(init_life);
         game
     }
-    fn adjecents(coord: &CellCoord) -> [CellCoord; 8] {
-        let [row, col] = coord;
-        [
-            [row - 1, col - 1],
-            [row - 1, col + 0],
-            [row - 1, col + 1],
-            //
-            [row + 0, col - 1],
-            // [row + 0, col + 0],
-            [row + 0, col + 1],
-            //
-            [row + 1, col - 1],
-            [row + 1, col + 0],
-            [row + 1, col + 1],
-        ]
+    /// neighbours of `coord` in the order of the rule's [Adjacency] offsets,
+    /// wrapped by its topology and none past a bounded edge
+    fn neighbours(coord: &CellCoord, rule: &Rule) -> impl Iterator<Item = Option<CellCoord>> {
+        let [row, col] = *coord;
+        let topology = rule.topology();
+        (rule.adjacency().offsets().iter()).map(move |[dr, dc]| topology.wrap([row + dr, col + dc]))
     }
-    fn cell_birth(&self, coord: &CellCoord) -> bool {
-        let adjs = Self::adjecents(coord);
-        let count = adjs
-            .into_iter()
-            .filter(|c| self.state.get(c).is_some())
-            .count() as u8;
-        count == 3
+    /// neighbours of `coord`, wrapped or clipped by the rule's topology
+    fn adjecents(coord: &CellCoord, rule: &Rule) -> impl Iterator<Item = CellCoord> {
+        Self::neighbours(coord, rule).flatten()
+    }
+    fn is_alive(&self, coord: &CellCoord) -> bool {
+        self.state.get(coord) == Some(&ALIVE)
+    }
+    /// state of `coord`, 0 when dead
+    fn state_of(&self, coord: &CellCoord) -> u8 {
+        self.state.get(coord).copied().unwrap_or(0)
+    }
+    fn live_cells(&self) -> impl Iterator<Item = &CellCoord> {
+        self.state
+            .iter()
+            .filter(|(_, state)| **state == ALIVE)
+            .map(|(cell, _)| cell)
+    }
+    /// which neighbours of `coord` are alive
+    fn live_adjecents(&self, coord: &CellCoord, rule: &Rule) -> Neighbourhood {
+        Self::neighbours(coord, rule)
+            .enumerate()
+            .filter(|(_, cell)| matches!(cell, Some(cell) if self.is_alive(cell)))
+            .fold(0, |neighbourhood, (i, _)| neighbourhood | 1 << i)
+    }
+    fn cell_birth(&self, coord: &CellCoord, rule: &Rule) -> bool {
+        rule.birth(self.live_adjecents(coord, rule))
     }
-    fn save_spawns(&mut self) {
+    fn save_spawns(&mut self, rule: &Rule) {
         // self.spawns.clear();
         self.buffer = self
-            .state
-            .iter()
-            .flat_map(|cell| Self::adjecents(cell))
+            .live_cells()
+            .flat_map(|cell| Self::adjecents(cell, rule))
             .filter(|cell| self.state.get(cell).is_none())
-            .filter(|cell| self.cell_birth(cell))
+            .filter(|cell| self.cell_birth(cell, rule))
+            .map(|cell| (cell, ALIVE))
             .collect();
     }
     fn insert_saved(&mut self) {
-        for cell in self.buffer.drain(..) {
-            self.state.insert(cell);
+        for (cell, state) in self.buffer.drain(..) {
+            let old = self.state.insert(cell, state);
+            // spawns next to several live cells are saved more than once
+            if let (Some(fingerprint), None) = (&mut self.fingerprint, old) {
+                fingerprint.update(cell, 0, state);
+            }
         }
     }
-    fn cell_survive(&self, coord: &CellCoord) -> bool {
-        let adjs = Self::adjecents(coord);
-        let count = adjs
-            .into_iter()
-            .filter(|c| self.state.get(c).is_some())
-            .count() as u8;
-        count == 2 || count == 3
+    fn cell_survive(&self, coord: &CellCoord, rule: &Rule) -> bool {
+        rule.survive(self.live_adjecents(coord, rule))
     }
-    fn kill_cells(&mut self) {
-        let survivors: Box<[_]> = self
+    /// live cells survive or start decaying, decaying cells decay further,
+    /// returning the cells that did not stay as they were
+    fn kill_cells(&mut self, rule: &Rule) -> CellVector {
+        let next: Box<[_]> = self
             .state
             .iter()
-            .filter(|cell| self.cell_survive(cell))
-            .copied()
+            .map(|(cell, &state)| match state {
+                ALIVE if self.cell_survive(cell, rule) => (*cell, ALIVE),
+                state => (*cell, rule.decay(state)),
+            })
             .collect();
+        let changed: CellVector = (next.iter())
+            .filter(|(cell, state)| self.state.get(cell) != Some(state))
+            .map(|(cell, _)| *cell)
+            .collect();
+        for (cell, state) in next.iter() {
+            let old = self.state_of(cell);
+            if old != *state {
+                self.changed(*cell, old, *state);
+            }
+        }
         self.state.clear();
-        self.state.extend(survivors);
+        self.state
+            .extend(next.iter().filter(|(_, state)| *state != 0).copied());
+        changed
     }
-    fn tick(&mut self) {
-        self.save_spawns();
-        self.kill_cells();
+    /// write the next generation of a one dimensional rule below the last one,
+    /// scrolling a finite world up once it reaches the bottom
+    fn grow_row(&mut self, wolfram: &Wolfram, topology: Topology) {
+        let corners = topology.corners();
+        let row = (self.row)
+            .or_else(|| Some(self.bounds()?[1][0]))
+            .or_else(|| Some(corners?[0][0]));
+        let Some(row) = row else {
+            return;
+        };
+        let live = (self.live_cells())
+            .filter(|cell| cell[0] == row)
+            .map(|cell| cell[1])
+            .collect();
+        let next = wolfram.next_row(&live, topology);
+        let mut row = row + 1;
+        if let Some([[top, _], [bottom, _]]) = corners {
+            if row > bottom {
+                self.move_cells(|[r, c]| (r > top).then_some([r - 1, c]));
+                row = bottom;
+            }
+        }
+        self.state
+            .extend(next.into_iter().map(|col| ([row, col], ALIVE)));
+        self.row = Some(row);
+    }
+    fn tick(&mut self, rule: &Rule) {
+        // only stepping with the transitions of the same rule keeps track
+        let active = self.active.take().filter(|active| active.rule == *rule);
+        // as do the transitions for the fingerprint, other steps redo it
+        let fingerprint = self.fingerprint.take();
+        if let Some(range) = rule.range() {
+            self.state = range.tick(&self.state, rule);
+            return;
+        }
+        if let Some(table) = rule.table() {
+            self.state = table.tick(&self.state, rule.topology());
+            return;
+        }
+        if let Some(wolfram) = rule.wolfram() {
+            self.grow_row(&wolfram, rule.topology());
+            return;
+        }
+        if let Some(margolus) = rule.margolus() {
+            self.state = margolus.step(&self.state, rule.topology(), self.odd);
+            self.odd = !self.odd;
+            return;
+        }
+        self.fingerprint = fingerprint;
+        #[cfg(feature = "parallel")]
+        if self.state.len() >= PARALLEL_CELLS
+            && active
+                .as_ref()
+                .map_or(true, |active| active.cells.len() >= PARALLEL_CELLS)
+        {
+            let threads = std::thread::available_parallelism().map_or(1, usize::from);
+            if threads > 1 {
+                self.tick_stripes(rule, threads);
+                return;
+            }
+        }
+        match active {
+            Some(active) => self.tick_active(rule, &active.cells),
+            None => self.tick_transitions(rule),
+        }
+    }
+    /// advance one generation of the `birth`/`survive` transitions
+    fn tick_transitions(&mut self, rule: &Rule) {
+        self.save_spawns(rule);
+        let mut changed: HashSet<CellCoord> = self.kill_cells(rule).into_iter().collect();
+        changed.extend(self.buffer.iter().map(|(cell, _)| *cell));
         self.insert_saved();
+        self.active = Some(Active {
+            rule: rule.clone(),
+            cells: changed,
+        });
+    }
+    /// advance one generation of the `birth`/`survive` transitions like
+    /// [Life::tick_transitions], looking only at the `changed` cells and
+    /// their neighbours, as the others see the same neighbourhoods as before
+    fn tick_active(&mut self, rule: &Rule, changed: &HashSet<CellCoord>) {
+        let around: HashSet<CellCoord> = (changed.iter())
+            .flat_map(|cell| std::iter::once(*cell).chain(Self::adjecents(cell, rule)))
+            .collect();
+        let steps: StateVector = around
+            .into_iter()
+            .filter_map(|cell| {
+                let state = self.state_of(&cell);
+                let next = match state {
+                    // like the full tick, only cells next to live ones are born
+                    0 => match self.live_adjecents(&cell, rule) {
+                        0 => 0,
+                        neighbours => u8::from(rule.birth(neighbours)),
+                    },
+                    ALIVE if self.cell_survive(&cell, rule) => ALIVE,
+                    state => rule.decay(state),
+                };
+                (next != state).then_some((cell, next))
+            })
+            .collect();
+        for (cell, state) in &steps {
+            let old = match state {
+                0 => self.state.remove(cell),
+                state => self.state.insert(*cell, *state),
+            };
+            self.changed(*cell, old.unwrap_or(0), *state);
+        }
+        self.active = Some(Active {
+            rule: rule.clone(),
+            cells: steps.into_iter().map(|(cell, _)| cell).collect(),
+        });
     }
-    fn toggle_cell(&mut self, coord: CellCoord) {
-        if !self.state.remove(&coord) {
-            self.state.insert(coord);
+    /// advance one generation of the `birth`/`survive` transitions like
+    /// [Life::tick_transitions], with the world cut into `stripes` bands of
+    /// rows stepped on their own threads
+    #[cfg(feature = "parallel")]
+    fn tick_stripes(&mut self, rule: &Rule, stripes: usize) {
+        let Some([[top, _], [bottom, _]]) = self.bounds() else {
+            return;
+        };
+        let height = (bottom - top) as usize / stripes + 1;
+        let mut bands = vec![Vec::new(); stripes];
+        for (cell, state) in &self.state {
+            bands[(cell[0] - top) as usize / height].push((*cell, *state));
+        }
+        let life = &*self;
+        let next: Vec<StateVector> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (bands.iter())
+                .map(|band| scope.spawn(move || life.step_cells(band, rule)))
+                .collect();
+            (threads.into_iter())
+                .map(|thread| thread.join().expect("stepping a stripe panicked"))
+                .collect()
+        });
+        let next: CellMap = next.into_iter().flatten().collect();
+        let mut changed: HashSet<CellCoord> = (next.iter())
+            .filter(|(cell, state)| self.state.get(*cell) != Some(*state))
+            .map(|(cell, _)| *cell)
+            .collect();
+        changed.extend(self.state.keys().filter(|cell| !next.contains_key(*cell)));
+        for cell in &changed {
+            let (old, new) = (self.state_of(cell), next.get(cell).copied().unwrap_or(0));
+            self.changed(*cell, old, new);
         }
+        self.state = next;
+        self.active = Some(Active {
+            rule: rule.clone(),
+            cells: changed,
+        });
+    }
+    /// next states of `cells` and the dead cells their live ones bring alive,
+    /// some of them more than once
+    #[cfg(feature = "parallel")]
+    fn step_cells(&self, cells: &[(CellCoord, u8)], rule: &Rule) -> StateVector {
+        let survivors = cells.iter().map(|(cell, state)| match *state {
+            ALIVE if self.cell_survive(cell, rule) => (*cell, ALIVE),
+            state => (*cell, rule.decay(state)),
+        });
+        let births = (cells.iter())
+            .filter(|(_, state)| *state == ALIVE)
+            .flat_map(|(cell, _)| Self::adjecents(cell, rule))
+            .filter(|cell| !self.state.contains_key(cell))
+            .filter(|cell| self.cell_birth(cell, rule))
+            .map(|cell| (cell, ALIVE));
+        survivors
+            .filter(|(_, state)| *state != 0)
+            .chain(births)
+            .collect()
+    }
+    /// advance one generation of `rule` made stochastic by `chances`, noise
+    /// flips cells of a finite world or around the live cells on a plane
+    fn tick_random(&mut self, rule: &Rule, chances: &Chances, random: &mut Random) {
+        let before = self.state.clone();
+        self.tick(rule);
+        let area = rule.topology().corners().or_else(|| {
+            let [[top, left], [bottom, right]] = self.bounds()?;
+            Some([[top - 1, left - 1], [bottom + 1, right + 1]])
+        });
+        chances.apply(&before, &mut self.state, area, random);
+        self.active = None;
+        self.fingerprint = None;
+    }
+    /// undo a generation of a reversible rule, returning whether it is one
+    fn tick_back(&mut self, rule: &Rule) -> bool {
+        let Some(margolus) = rule.margolus() else {
+            return false;
+        };
+        self.odd = !self.odd;
+        self.active = None;
+        self.fingerprint = None;
+        self.state = margolus
+            .inverse()
+            .step(&self.state, rule.topology(), self.odd);
+        true
     }
+    /// set `coord` to `state`, 0 kills it, returning the state it had
+    fn set_state(&mut self, coord: CellCoord, state: u8) -> u8 {
+        self.row = None;
+        if let Some(active) = &mut self.active {
+            active.cells.insert(coord);
+        }
+        let old = match state {
+            0 => self.state.remove(&coord),
+            state => self.state.insert(coord, state),
+        };
+        let old = old.unwrap_or(0);
+        self.changed(coord, old, state);
+        old
+    }
+    /// keep the fingerprint up to date with `cell` going from `old` to `new`
+    fn changed(&mut self, cell: CellCoord, old: u8, new: u8) {
+        if let Some(fingerprint) = &mut self.fingerprint {
+            fingerprint.update(cell, old, new);
+        }
+    }
+    /// cells born and cells that died or started decaying in the last
+    /// generation, none when it did not keep track of the cells it changed
+    fn last_changes(&self) -> Option<[u64; 2]> {
+        let active = self.active.as_ref()?;
+        // only live cells start decaying, and only dead ones are born
+        let dying = active.rule.decay(ALIVE);
+        let count = |state: u8| {
+            (active.cells.iter())
+                .filter(|cell| self.state_of(cell) == state)
+                .count() as u64
+        };
+        Some([count(ALIVE), count(dying)])
+    }
+    /// fingerprint of the world, worked out again when unset
+    fn fingerprint(&mut self) -> Fingerprint {
+        let state = &self.state;
+        *(self.fingerprint).get_or_insert_with(|| Fingerprint::of(state))
+    }
+    /// every cell that is not dead
     fn state_as_list(&self) -> CellVector {
-        self.state.iter().copied().collect()
+        self.state.keys().copied().collect()
+    }
+    fn cell_states(&self) -> StateVector {
+        self.state
+            .iter()
+            .map(|(cell, state)| (*cell, *state))
+            .collect()
+    }
+    fn load(&mut self, cells: &[CellCoord]) {
+        self.clear();
+        self.add_cells(cells);
+    }
+    /// replace the world with `cells`, leaving dead those in states a rule
+    /// of `states` does not have, returning how many were
+    fn load_states(&mut self, cells: &[(CellCoord, u8)], states: u8) -> usize {
+        self.clear();
+        self.fingerprint = None;
+        let (known, unknown): (StateVector, StateVector) = (cells.iter())
+            .filter(|(_, state)| *state != 0)
+            .partition(|(_, state)| *state < states);
+        self.state.extend(known);
+        unknown.len()
+    }
+    /// fold cells into the world of `topology`, dropping those it has no place for
+    fn fit(&mut self, topology: Topology) {
+        self.move_cells(|cell| topology.wrap(cell));
+    }
+    /// apgcode of the live cells when run with `rule`, the least of their 8
+    /// orientations in the phase they are in
+    fn apgcode(&self, rule: &Rule) -> String {
+        let cells: CellVector = self.live_cells().copied().collect();
+        let kind = (census::runs(rule))
+            .then(|| census::classify(&cells, rule))
+            .flatten();
+        apgcode::encode(kind.map(|(kind, _)| kind), &[cells])
+    }
+    /// top left and bottom right cells that are not dead, none for an empty world
+    fn bounds(&self) -> Option<[CellCoord; 2]> {
+        let first = *self.state.keys().next()?;
+        Some(self.state.keys().fold([first, first], |[min, max], cell| {
+            [
+                [min[0].min(cell[0]), min[1].min(cell[1])],
+                [max[0].max(cell[0]), max[1].max(cell[1])],
+            ]
+        }))
+    }
+}
+
+/// A world that can be advanced in time
+pub trait Simulation {
+    /// advance one generation
+    fn tick(&mut self, rule: &Rule);
+    /// advance 2^pow generations
+    fn step_pow(&mut self, rule: &Rule, pow: u32) {
+        for _ in 0..1_u64 << pow {
+            self.tick(rule);
+        }
+    }
+    /// replace the world with `cells`
+    fn load(&mut self, cells: &[CellCoord]);
+    fn cells(&self) -> CellVector;
+    /// some cells went past the i32 coordinates [Simulation::cells] gives
+    fn overflows(&self) -> bool {
+        false
+    }
+}
+
+impl Simulation for Life {
+    fn tick(&mut self, rule: &Rule) {
+        Life::tick(self, rule);
+    }
+    fn load(&mut self, cells: &[CellCoord]) {
+        Life::load(self, cells);
+    }
+    fn cells(&self) -> CellVector {
+        self.state_as_list()
     }
 }
 
@@ -193,6 +625,16 @@ impl Life {
 mod test_life {
     use super::*;
 
+    /// the serial tick and, with the `parallel` feature, the one stepping
+    /// stripes of the world on threads
+    fn tick_paths() -> Vec<fn(&mut Life, &Rule)> {
+        #[allow(unused_mut)]
+        let mut paths: Vec<fn(&mut Life, &Rule)> = vec![Life::tick_transitions];
+        #[cfg(feature = "parallel")]
+        paths.push(|life, rule| life.tick_stripes(rule, 3));
+        paths
+    }
+
     #[test]
     fn json_life() {
         let life = Life::glider();
@@ -229,11 +671,13 @@ mod test_life {
     #[test]
     /// make sure static life is static
     fn test_tub() {
-        let mut life = Life::tub();
-        let expected = life.clone();
-        for _ in 0..17 {
-            life.tick();
-            assert_eq!(life, expected);
+        for tick in tick_paths() {
+            let mut life = Life::tub();
+            let expected = life.clone();
+            for _ in 0..17 {
+                tick(&mut life, &Rule::default());
+                assert_eq!(life, expected);
+            }
         }
     }
 
@@ -242,7 +686,7 @@ mod test_life {
         let mut life = Life::tub();
         life.translate(&[5, 5]);
         life.translate(&[5, 5]);
-        let mut tick: Vec<_> = life.state.iter().copied().collect();
+        let mut tick: Vec<_> = life.state.keys().copied().collect();
         tick.sort();
         insta::assert_ron_snapshot!(tick, @r#"
         [
@@ -255,41 +699,782 @@ mod test_life {
     }
 
     #[test]
-    fn test_blinker_tick() {
-        let mut life = Life::blinker();
-        {
-            life.tick();
-            let mut cells: Vec<_> = life.state.iter().copied().collect();
+    /// the B36 in HighLife turns a lone blinker into a replicator
+    fn test_highlife_tick() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let mut life = Life::new(&[[0, 1], [0, 2], [1, 0], [1, 2], [2, 0], [2, 1]]);
+        let mut conway = life.clone();
+        life.tick(&rule);
+        conway.tick(&Rule::default());
+        assert_eq!(life.state.len(), conway.state.len() + 1);
+        assert!(life.state.contains_key(&[1, 1]));
+    }
+
+    #[test]
+    /// non-totalistic rules treat every rotation and reflection of a pattern alike
+    fn test_isotropic_tick() {
+        let rule: Rule = "B2n3/S23-q".parse().unwrap();
+        let soup = Life::new(&[
+            [0, 0],
+            [0, 1],
+            [0, 3],
+            [1, 1],
+            [2, 0],
+            [2, 2],
+            [2, 3],
+            [3, 1],
+        ]);
+        let sorted = |life: &Life| {
+            let mut cells = life.state_as_list();
             cells.sort();
-            insta::assert_ron_snapshot!(cells, @r#"
-            [
-              (-1, 0),
-              (0, 0),
-              (1, 0),
-            ]
-            "#);
+            cells
+        };
+        let mut expected = soup.clone();
+        for _ in 0..8 {
+            expected.tick(&rule);
         }
-        {
-            life.tick();
-            let mut tick: Vec<_> = life.state.iter().copied().collect();
-            tick.sort();
-            insta::assert_ron_snapshot!(tick, @r#"
-            [
-              (0, -1),
-              (0, 0),
-              (0, 1),
+        for rotation in [Rotation::R90, Rotation::R180, Rotation::R270] {
+            let mut life = soup.clone();
+            life.rotate(rotation);
+            life.flip_cols();
+            for _ in 0..8 {
+                life.tick(&rule);
+            }
+            life.flip_cols();
+            life.rotate(match rotation {
+                Rotation::R90 => Rotation::R270,
+                Rotation::R270 => Rotation::R90,
+                rotation => rotation,
+            });
+            assert_eq!(sorted(&life), sorted(&expected), "{rotation:?}");
+        }
+        assert!(!expected.state.is_empty());
+    }
+
+    #[test]
+    /// a lone cell seeds each of its neighbours and dies
+    fn test_adjacency_tick() {
+        for (rule, expected) in [
+            ("B1/SV", vec![[-1, 0], [0, -1], [0, 1], [1, 0]]),
+            (
+                "B1/SH",
+                vec![[-1, 0], [-1, 1], [0, -1], [0, 1], [1, -1], [1, 0]],
+            ),
+        ] {
+            let mut life = Life::new(&[[0, 0]]);
+            life.tick(&rule.parse().unwrap());
+            let mut cells = life.state_as_list();
+            cells.sort();
+            assert_eq!(cells, expected, "{rule}");
+        }
+    }
+
+    #[test]
+    /// in Brian's Brain live cells always start dying and dying cells then die
+    fn test_brians_brain_tick() {
+        let rule: Rule = "/2/3".parse().unwrap();
+        let mut life = Life::new(&[[0, 0], [0, 1]]);
+        let sorted = |life: &Life| {
+            let mut cells = life.cell_states();
+            cells.sort();
+            cells
+        };
+        life.tick(&rule);
+        assert_eq!(
+            sorted(&life),
+            vec![
+                ([-1, 0], ALIVE),
+                ([-1, 1], ALIVE),
+                ([0, 0], 2),
+                ([0, 1], 2),
+                ([1, 0], ALIVE),
+                ([1, 1], ALIVE),
             ]
-            "#);
+        );
+        life.tick(&rule);
+        let dying = life.cell_states().iter().filter(|(_, s)| *s == 2).count();
+        assert_eq!((life.live_cells().count(), dying), (6, 4));
+        assert_eq!(life.state_of(&[0, 0]), 0);
+        assert!(life.is_alive(&[0, -1]));
+    }
+
+    #[test]
+    /// a glider crosses the edges of a torus and comes back to where it started
+    fn test_torus_glider() {
+        let torus = Rule::default().with_topology("T8,6".parse().unwrap());
+        let start = Life::glider();
+        let mut life = start.clone();
+        for _ in 0..4 * 24 {
+            life.tick(&torus);
+            assert_eq!(life.state.len(), 5);
+        }
+        assert_eq!(life.state, start.state);
+    }
+
+    #[test]
+    /// bounded and twisted worlds keep every cell inside their edges
+    fn test_finite_worlds() {
+        for topology in ["P8,6", "K8*,6", "C8,6"] {
+            let rule = Rule::default().with_topology(topology.parse().unwrap());
+            let [min, max] = rule.topology().corners().unwrap();
+            let edges = Rect { min, max };
+            let mut life = Life::glider();
+            for _ in 0..64 {
+                life.tick(&rule);
+                assert!(
+                    life.state.keys().all(|cell| edges.contains(cell)),
+                    "{topology}"
+                );
+            }
+        }
+        // in a bounded box the glider ends up as a block in a corner
+        let bounded = Rule::default().with_topology("P8,6".parse().unwrap());
+        let mut life = Life::glider();
+        for _ in 0..64 {
+            life.tick(&bounded);
         }
+        assert_eq!(life.state.len(), 4);
     }
+
+    #[test]
+    fn test_blinker_tick() {
+        for tick in tick_paths() {
+            insta::allow_duplicates! {
+                let mut life = Life::blinker();
+                {
+                    tick(&mut life, &Rule::default());
+                    let mut cells: Vec<_> = life.state.keys().copied().collect();
+                    cells.sort();
+                    insta::assert_ron_snapshot!(cells, @r#"
+                    [
+                      (-1, 0),
+                      (0, 0),
+                      (1, 0),
+                    ]
+                    "#);
+                }
+                {
+                    tick(&mut life, &Rule::default());
+                    let mut tick: Vec<_> = life.state.keys().copied().collect();
+                    tick.sort();
+                    insta::assert_ron_snapshot!(tick, @r#"
+                    [
+                      (0, -1),
+                      (0, 0),
+                      (0, 1),
+                    ]
+                    "#);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    /// soups of two and three state rules step the same on every count of
+    /// stripes
+    fn test_stripes() {
+        let rect = Rect::from_corners([-40, -40], [40, 40]);
+        let cells: StateVector = soup::fill(&rect, 0.4, 9, Symmetry::C1);
+        for rule in ["B3/S23", "/2/3", "B2n3/S23-q"] {
+            let rule: Rule = rule.parse().unwrap();
+            let mut serial = Life::empty();
+            serial.load_states(&cells, rule.states());
+            serial.fingerprint();
+            let mut striped = [serial.clone(), serial.clone(), serial.clone()];
+            for _ in 0..4 {
+                serial.tick_transitions(&rule);
+                for (life, stripes) in striped.iter_mut().zip([1, 4, 7]) {
+                    life.tick_stripes(&rule, stripes);
+                    assert_eq!(life.state, serial.state, "{rule} in {stripes}");
+                    assert_eq!(life.fingerprint, Some(Fingerprint::of(&life.state)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// stepping only around the cells that changed gives the same worlds as
+    /// looking at every cell, even after edits
+    fn test_active_cells() {
+        let rect = Rect::from_corners([-12, -12], [12, 12]);
+        let cells = soup::fill(&rect, 0.45, 4, Symmetry::C1);
+        for rule in [
+            "B3/S23",
+            "B36/S23:T20,16",
+            "/2/3:K20*,16",
+            "B2n3/S23-q:P24,24",
+            "B2/S34H",
+            "B2/S013V:C20,20",
+        ] {
+            let rule: Rule = rule.parse().unwrap();
+            let mut life = Life::empty();
+            life.load_states(&cells, rule.states());
+            life.fit(rule.topology());
+            life.fingerprint();
+            let mut full = life.clone();
+            for generation in 0..40 {
+                if generation == 20 {
+                    for cell in [[0, 0], [0, 1], [1, 1], [5, -3]] {
+                        let state = ALIVE - life.state_of(&cell).min(ALIVE);
+                        life.set_state(cell, state);
+                        full.set_state(cell, state);
+                    }
+                }
+                let before = life.state.clone();
+                life.tick(&rule);
+                let changes = Stats::compare(&before, &life.state);
+                assert_eq!(life.last_changes(), Some(changes), "{rule}");
+                full.active = None;
+                full.tick(&rule);
+                assert_eq!(life.state, full.state, "{rule} generation {generation}");
+                // kept up to date along the way
+                assert_eq!(life.fingerprint, Some(Fingerprint::of(&life.state)));
+            }
+        }
+
+        // still lifes cost nothing and a blinker only its own cells
+        let mut life = Life::blinker() | Life::new(&[[5, 5], [5, 6], [6, 5], [6, 6]]);
+        for _ in 0..3 {
+            life.tick(&Rule::default());
+        }
+        assert_eq!(life.active.map(|active| active.cells.len()), Some(4));
+    }
+
+    #[test]
+    /// births and deaths that only happen by chance, and noise, repeat with
+    /// the seed
+    fn test_stochastic_tick() {
+        let rule = Rule::default();
+        let no_births = Chances {
+            birth: 0.0,
+            ..Default::default()
+        };
+        let mut life = Life::blinker();
+        life.tick_random(&rule, &no_births, &mut Random::new(1));
+        assert_eq!(life.state_as_list(), [[0, 0]]);
+        let no_deaths = Chances {
+            death: 0.0,
+            ..Default::default()
+        };
+        let mut life = Life::blinker();
+        life.tick_random(&rule, &no_deaths, &mut Random::new(1));
+        assert_eq!(life.state.len(), 5);
+
+        let run = |seed: u64| {
+            let chances = Chances {
+                birth: 0.5,
+                death: 0.5,
+                noise: 0.05,
+            };
+            let torus = rule.clone().with_topology("T8,6".parse().unwrap());
+            let (mut life, mut random) = (Life::glider(), Random::new(seed));
+            for _ in 0..4 {
+                life.tick_random(&torus, &chances, &mut random);
+            }
+            let mut cells = life.state_as_list();
+            cells.sort();
+            cells
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+        insta::assert_ron_snapshot!(run(7), @r#"
+        [
+          (-2, -4),
+          (-1, 1),
+        ]
+        "#);
+    }
+}
+
+/// Simulation engine used to step the world
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Engine {
+    /// neighbour counting over a set of live cells
+    #[default]
+    Naive,
+    /// memoized quadtree, fast for large and periodic worlds
+    HashLife,
+    /// bit packed 64x64 tiles, fast for dense soups
+    Tiles,
 }
 
 #[derive(Default)]
 pub struct Model {
     life: Life,
+    rule: Rule,
+    engine: Engine,
+    hashlife: HashLife,
+    tiles: Tiles,
+    /// rule tables loaded so far, for rules naming them
+    tables: Tables,
+    /// fingerprint of the world the selected engine holds, it is loaded
+    /// again once `life` is edited away from it
+    engine_world: Option<Fingerprint>,
+    /// comments of the last loaded pattern, kept for export
+    comments: Comments,
+    runner: Runner,
+    stats: Stats,
+    history: History,
+    selection: Option<Rect>,
+    /// copied cells relative to the top left of the rectangle they fill
+    clipboard: Option<(Life, Rect)>,
+    /// draw copies around a wrapping world
+    show_wrapped: bool,
+    /// state clicked cells are painted with, none to toggle them
+    paint: Option<u8>,
+    /// probabilities making the rule stochastic
+    chances: Chances,
+    /// seed `random` started from
+    seed: u64,
+    random: Random,
+    /// generations of the run, to find when it repeats
+    cycles: Cycles,
+    /// objects counted by the last census
+    census: Option<Census>,
     camera: Camera,
 }
 
+/// most copies of a wrapping world drawn around it
+const MAX_WRAPPED_COPIES: usize = 64;
+
+const OUT_OF_BOUNDS: &str = "The selection would go past the largest cell coordinates";
+const WORLD_OUT_OF_BOUNDS: &str = "Stopped before the world went past the largest cell coordinates";
+
+/// largest jump of the engines that step one generation at a time
+const MAX_LOOP_STEP_POW: u32 = 12;
+
+const HASHLIFE_UNSUPPORTED: &str = "HashLife only runs two state range 1 B/S rules without \
+    chances on an infinite plane, other worlds are stepped with the naive engine";
+
+const TILES_UNSUPPORTED: &str = "Tiles only run two state B/S rules without chances that \
+    count neighbours, on an infinite plane, other worlds are stepped with the naive engine";
+
+/// Progress of the simulation since the world was loaded
+#[derive(Default)]
+struct Stats {
+    generation: u64,
+    /// cells born by the last step
+    births: u64,
+    /// cells that died or started decaying in the last step
+    deaths: u64,
+    /// population after each of the last [Stats::HISTORY_LENGTH] steps, oldest first
+    history: VecDeque<u64>,
+}
+
+impl Stats {
+    const HISTORY_LENGTH: usize = 512;
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+    /// cells born and cells that died or started decaying from `before` to
+    /// `after`
+    fn compare(before: &CellMap, after: &CellMap) -> [u64; 2] {
+        let left = |from: &CellMap, to: &CellMap| {
+            from.iter()
+                .filter(|(cell, state)| **state == ALIVE && to.get(*cell) != Some(&ALIVE))
+                .count() as u64
+        };
+        [left(after, before), left(before, after)]
+    }
+    /// record a step of `generations` leaving `population` cells
+    fn record(&mut self, generations: u64, [births, deaths]: [u64; 2], population: usize) {
+        self.generation += generations;
+        self.births = births;
+        self.deaths = deaths;
+        if self.history.len() == Self::HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(population as u64);
+    }
+}
+
+/// Core driven run loop, stepping the world on timer wake ups
+struct Runner {
+    running: bool,
+    /// generations per second
+    speed: f32,
+    /// delay between frames
+    frame_millis: u64,
+    generations_per_frame: u32,
+    /// id of the pending wake up, stale ones are ignored
+    frame: u64,
+    /// step reversible rules back in time
+    backwards: bool,
+    /// stop running once the world repeats
+    pause_on_cycle: bool,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        let mut runner = Self {
+            running: false,
+            speed: 0.0,
+            frame_millis: 0,
+            generations_per_frame: 0,
+            frame: 0,
+            backwards: false,
+            pause_on_cycle: false,
+        };
+        runner.set_speed(60.0);
+        runner
+    }
+}
+
+impl Runner {
+    /// shortest delay between frames, about one screen refresh
+    const MIN_FRAME_MILLIS: f32 = 16.0;
+    const MIN_SPEED: f32 = 0.1;
+    const MAX_SPEED: f32 = 100_000.0;
+
+    fn set_speed(&mut self, speed: f32) {
+        let speed = speed.clamp(Self::MIN_SPEED, Self::MAX_SPEED);
+        let millis = 1000.0 / speed;
+        if millis >= Self::MIN_FRAME_MILLIS {
+            self.frame_millis = millis.round() as u64;
+            self.generations_per_frame = 1;
+        } else {
+            self.frame_millis = Self::MIN_FRAME_MILLIS as u64;
+            self.generations_per_frame = (Self::MIN_FRAME_MILLIS / millis).round() as u32;
+        }
+        self.speed = speed;
+    }
+    /// start a new frame sequence, invalidating pending wake ups
+    fn next_frame(&mut self) -> u64 {
+        self.frame += 1;
+        self.frame
+    }
+}
+
+impl Model {
+    fn export(&self, format: FileFormat) -> Vec<u8> {
+        let cells = self.life.cell_states();
+        format::encode(format, &cells, &self.rule, &self.comments)
+    }
+    /// advance the world 2^pow generations with the selected engine, or go
+    /// back as many when running a reversible rule backwards. False when
+    /// cells would go past the largest coordinates, leaving the world as it was.
+    fn advance(&mut self, pow: u32) -> bool {
+        self.advance_by(1 << pow)
+    }
+    /// advance the world `generations` at once, see [Model::advance]
+    fn advance_by(&mut self, generations: u64) -> bool {
+        if self.runner.backwards && self.rule.margolus().is_some() {
+            for _ in 0..generations {
+                if !self.reverse() {
+                    self.runner.running = false;
+                    break;
+                }
+            }
+            return true;
+        }
+        if self.history.checkpoint_due(self.stats.generation) {
+            self.history.checkpoint(self.snapshot());
+        }
+        // a generation of transitions keeps track of the cells it changes,
+        // other steps are compared with the world before them
+        let before = (generations > 1 || !self.tracks_changes()).then(|| self.life.state.clone());
+        self.observe_cycle();
+        if !self.step_world(generations) {
+            self.runner.running = false;
+            return false;
+        }
+        let changes = match &before {
+            Some(before) => Stats::compare(before, &self.life.state),
+            None => self.life.last_changes().unwrap_or_default(),
+        };
+        (self.stats).record(generations, changes, self.life.state.len());
+        self.observe_cycle();
+        true
+    }
+    /// remember the world to find when the run repeats, unless its next
+    /// generation depends on more than its cells
+    fn observe_cycle(&mut self) {
+        let rule = &self.rule;
+        if self.chances.is_certain() && rule.wolfram().is_none() && rule.margolus().is_none() {
+            let fingerprint = self.life.fingerprint();
+            self.cycles
+                .observe(rule, self.stats.generation, fingerprint);
+        }
+    }
+    /// cycle the current run settled into, none when it has not yet or the
+    /// world changed since
+    fn cycle(&self) -> Option<Cycle> {
+        let fingerprint = self.life.fingerprint?;
+        (self.cycles).found(&self.rule, self.stats.generation, fingerprint)
+    }
+    /// step `life` with the selected engine, which keeps its world between
+    /// steps and only loads it again after edits. False when the engine's
+    /// cells went past i32 coordinates, `life` is then left as it was.
+    fn step_world(&mut self, generations: u64) -> bool {
+        self.life.odd = self.rule.margolus().is_some() && self.stats.generation % 2 == 1;
+        if !self.chances.is_certain() {
+            for _ in 0..generations {
+                self.life
+                    .tick_random(&self.rule, &self.chances, &mut self.random);
+            }
+            return true;
+        }
+        let rule = &self.rule;
+        let pows = (0..u64::BITS).filter(|pow| generations & 1 << pow != 0);
+        let engine: &mut dyn Simulation = match self.engine {
+            Engine::HashLife if !self.engine_unsupported() => &mut self.hashlife,
+            Engine::Tiles if !self.engine_unsupported() => &mut self.tiles,
+            // hashlife and tiles have no edges or decaying cells, such worlds
+            // always use the naive engine
+            Engine::Naive | Engine::HashLife | Engine::Tiles => {
+                pows.for_each(|pow| self.life.step_pow(rule, pow));
+                return true;
+            }
+        };
+        if self.engine_world != Some(self.life.fingerprint()) {
+            let cells: CellVector = self.life.live_cells().copied().collect();
+            engine.load(&cells);
+        }
+        pows.for_each(|pow| engine.step_pow(rule, pow));
+        if engine.overflows() {
+            // loaded again from `life` on the next step
+            self.engine_world = None;
+            return false;
+        }
+        self.life.load(&engine.cells());
+        self.engine_world = Some(self.life.fingerprint());
+        true
+    }
+    /// stepping the world goes through [Life::tick] with the transitions
+    fn tracks_changes(&self) -> bool {
+        self.rule.uses_transitions() && self.chances.is_certain() && !self.engine_runs()
+    }
+    /// hashlife or tiles run the current world
+    fn engine_runs(&self) -> bool {
+        self.engine != Engine::Naive && !self.engine_unsupported()
+    }
+    /// cells of the copies around a wrapping world inside `min..=max`
+    fn wrapped_copies(&self, min: CellCoord, max: CellCoord) -> StateVector {
+        let topology = self.rule.topology();
+        let (Some([[top, left], _]), Some([height, width])) = (topology.corners(), topology.size())
+        else {
+            return Vec::new();
+        };
+        if !self.show_wrapped || matches!(topology, Topology::Bounded { .. }) {
+            return Vec::new();
+        }
+        let tile = |coord: i32, start: i32, size: u32| (coord - start).div_euclid(size as i32);
+        let rows = tile(min[0], top, height)..=tile(max[0], top, height);
+        let cols = tile(min[1], left, width)..=tile(max[1], left, width);
+        // zoomed far out the copies are too small to be worth drawing
+        if rows.clone().count() * cols.clone().count() > MAX_WRAPPED_COPIES {
+            return Vec::new();
+        }
+        let visible = Rect { min, max };
+        rows.flat_map(|row| cols.clone().map(move |col| [row, col]))
+            .filter(|tiles| *tiles != [0, 0])
+            .flat_map(|tiles| {
+                self.life
+                    .state
+                    .iter()
+                    .filter_map(move |(cell, state)| Some((topology.image(*cell, tiles)?, *state)))
+            })
+            .filter(|(cell, _)| visible.contains(cell))
+            .collect()
+    }
+    /// selected engine can not run the current rule
+    fn engine_unsupported(&self) -> bool {
+        match self.engine {
+            Engine::Naive => false,
+            Engine::HashLife => {
+                self.rule.topology() != Topology::Plane
+                    || !self.chances.is_certain()
+                    || self.rule.states() > 2
+                    || !self.rule.uses_transitions()
+            }
+            Engine::Tiles => !self.chances.is_certain() || !tiles::runs(&self.rule),
+        }
+    }
+    /// largest jump the engine running the current world takes at once
+    fn max_step_pow(&self) -> u32 {
+        let backwards = self.runner.backwards && self.rule.margolus().is_some();
+        match self.engine {
+            Engine::HashLife if !backwards && !self.engine_unsupported() => hashlife::MAX_STEP_POW,
+            Engine::Naive | Engine::HashLife | Engine::Tiles => MAX_LOOP_STEP_POW,
+        }
+    }
+    /// what the selected engine runs, when it can not run the current rule
+    const fn engine_warning(&self) -> &'static str {
+        match self.engine {
+            Engine::Tiles => TILES_UNSUPPORTED,
+            Engine::Naive | Engine::HashLife => HASHLIFE_UNSUPPORTED,
+        }
+    }
+    /// change the rule as an undoable change, folding the world into a new
+    /// topology. The change ends the current run, which steps back by
+    /// replaying with the rule it was stepped with.
+    fn set_rule(&mut self, rule: Rule) {
+        if rule != self.rule {
+            self.history.push(Entry::Replace(self.snapshot()));
+        }
+        if rule.topology() != self.rule.topology() {
+            self.life.fit(rule.topology());
+        }
+        self.switch_rule(rule);
+    }
+    /// change the rule, leaving the world to the caller
+    fn switch_rule(&mut self, rule: Rule) {
+        self.camera.layout = rule.adjacency().layout();
+        if self.paint.map_or(false, |paint| paint >= rule.states()) {
+            self.paint = None;
+        }
+        self.rule = rule;
+        self.cycles.reset();
+    }
+    /// `cells` wrapped into the world, the last state given for a cell wins
+    fn wrapped(&self, cells: impl IntoIterator<Item = (CellCoord, u8)>) -> StateVector {
+        let topology = self.rule.topology();
+        let wrapped: CellMap = cells
+            .into_iter()
+            .filter_map(|(cell, state)| Some((topology.wrap(cell)?, state)))
+            .collect();
+        let mut wrapped: StateVector = wrapped.into_iter().collect();
+        wrapped.sort_unstable();
+        wrapped
+    }
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.life.cell_states(),
+            rule: self.rule.clone(),
+            generation: self.stats.generation,
+            random: self.random,
+            chances: self.chances,
+            seed: self.seed,
+        }
+    }
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.life
+            .load_states(&snapshot.cells, snapshot.rule.states());
+        self.switch_rule(snapshot.rule);
+        self.stats.generation = snapshot.generation;
+        self.random = snapshot.random;
+        self.chances = snapshot.chances;
+        self.seed = snapshot.seed;
+        self.stats.births = 0;
+        self.stats.deaths = 0;
+    }
+    /// bring dead cells among `cells` to their states as one undoable edit
+    fn stamp(&mut self, cells: StateVector) {
+        let mut born = self.wrapped(cells);
+        born.retain(|(cell, _)| self.life.state_of(cell) == 0);
+        self.edit(born);
+    }
+    /// replace the cells inside any of `rects` with `cells`, as one undoable
+    /// edit
+    fn replace_cells(&mut self, rects: &[Rect], cells: &Life) {
+        let mut cells = cells.clone();
+        cells.fit(self.rule.topology());
+        let cleared: StateVector = self
+            .life
+            .state
+            .keys()
+            .filter(|cell| rects.iter().any(|rect| rect.contains(cell)))
+            .filter(|cell| !cells.state.contains_key(*cell))
+            .map(|cell| (*cell, 0))
+            .collect();
+        self.edit(cleared.into_iter().chain(cells.cell_states()));
+    }
+    /// transform the selected cells in place with `f`, which returns the new
+    /// selection, false when that would go past the largest coordinates
+    fn transform_selection(&mut self, f: impl FnOnce(&mut Life, &Rect) -> Option<Rect>) -> bool {
+        let Some(rect) = self.selection else {
+            return true;
+        };
+        let mut cells = selection::copy(&self.life, &rect);
+        let Some(moved) = f(&mut cells, &rect) else {
+            return false;
+        };
+        // a turned selection covers cells outside the one it came from
+        self.replace_cells(&[rect, moved], &cells);
+        self.selection = Some(moved);
+        true
+    }
+    /// set the states of `cells` as one undoable edit
+    fn edit(&mut self, cells: impl IntoIterator<Item = (CellCoord, u8)>) {
+        let mut changed = self.wrapped(cells);
+        changed.retain(|(cell, state)| self.life.state_of(cell) != *state);
+        if changed.is_empty() {
+            return;
+        }
+        for (cell, state) in changed.iter_mut() {
+            *state = self.life.set_state(*cell, *state);
+        }
+        self.history.push(Entry::Edit(changed));
+    }
+    /// kill `cell` or bring it alive, or paint it with the paint state, as an
+    /// undoable edit
+    fn toggle(&mut self, cell: CellCoord) {
+        let Some(cell) = self.rule.topology().wrap(cell) else {
+            return;
+        };
+        let state = match (self.paint, self.life.state_of(&cell)) {
+            (Some(paint), _) => paint,
+            (None, 0) => ALIVE,
+            (None, _) => 0,
+        };
+        self.edit([(cell, state)]);
+    }
+    /// revert `entry`, returning the entry that reverts it back
+    fn revert(&mut self, entry: Entry) -> Entry {
+        match entry {
+            Entry::Edit(mut cells) => {
+                for (cell, state) in cells.iter_mut() {
+                    *state = self.life.set_state(*cell, *state);
+                }
+                Entry::Edit(cells)
+            }
+            Entry::Replace(snapshot) => {
+                let current = self.snapshot();
+                self.restore(snapshot);
+                Entry::Replace(current)
+            }
+            Entry::Run(mut checkpoints) => {
+                let current = self.snapshot();
+                checkpoints.truncate(1);
+                self.restore(checkpoints.pop().unwrap());
+                Entry::Replace(current)
+            }
+        }
+    }
+    /// compute the generation before this one with a reversible rule, false
+    /// at generation 0
+    fn reverse(&mut self) -> bool {
+        let Some(generation) = self.stats.generation.checked_sub(1) else {
+            return false;
+        };
+        let before = self.life.state.clone();
+        self.life.odd = generation % 2 == 0;
+        if !self.life.tick_back(&self.rule) {
+            return false;
+        }
+        let changes = Stats::compare(&before, &self.life.state);
+        (self.stats).record(0, changes, self.life.state.len());
+        self.stats.generation = generation;
+        true
+    }
+    /// go back one generation by replaying from the checkpoint before it
+    fn step_back(&mut self) -> bool {
+        let generation = self.stats.generation;
+        let Some(checkpoint) = self.history.step_back(generation) else {
+            return false;
+        };
+        let replay = generation - 1 - checkpoint.generation;
+        self.restore(checkpoint);
+        // the world reached this generation before, so it stays in bounds
+        self.step_world(replay);
+        self.stats.generation = generation - 1;
+        self.stats.history.pop_back();
+        true
+    }
+}
+
 struct Camera {
     /// halfsize of screen
     screen_size: Vec2,
@@ -299,6 +1484,7 @@ struct Camera {
     drag_start: Vec2,
     /// (world_size) * zoom = screen_size
     zoom: f32,
+    layout: Layout,
 }
 impl Default for Camera {
     fn default() -> Self {
@@ -309,21 +1495,34 @@ impl Default for Camera {
             pan,
             drag_start: Vec2::new(0.0, 0.0),
             zoom: 1.0,
+            layout: Layout::Square,
         }
     }
 }
 impl Camera {
     /// cell size in world
     const CELL_SIZE: f32 = 30.0;
+    /// distance between rows of hexagons in world, sqrt(3) / 2 of their width
+    const HEX_ROW_HEIGHT: f32 = Self::CELL_SIZE * 0.866_025_4;
     /// cell size in screen space
     const fn cell_size(&self) -> f32 {
         Self::CELL_SIZE * self.zoom
     }
 
-    const fn cell2world(&self, cell: &CellCoord) -> Vec2 {
-        let x = cell[1] as f32 * Self::CELL_SIZE;
-        let y = cell[0] as f32 * Self::CELL_SIZE;
-        Vec2::new(x, y)
+    /// top left of the cell, or of the box around a hexagon
+    fn cell2world(&self, cell: &CellCoord) -> Vec2 {
+        match self.layout {
+            Layout::Square => {
+                let x = cell[1] as f32 * Self::CELL_SIZE;
+                let y = cell[0] as f32 * Self::CELL_SIZE;
+                Vec2::new(x, y)
+            }
+            Layout::Hexagonal => {
+                let x = (cell[1] as f32 + cell[0] as f32 / 2.0) * Self::CELL_SIZE;
+                let y = cell[0] as f32 * Self::HEX_ROW_HEIGHT;
+                Vec2::new(x, y)
+            }
+        }
     }
     fn world2screen(&self, world_pos: &Vec2) -> Vec2 {
         let screen = (world_pos - self.pan) * self.zoom; //+ self.screen_size;
@@ -338,9 +1537,30 @@ impl Camera {
         (screen_pos) / self.zoom + self.pan
     }
     fn world2cell(&self, world_pos: &Vec2) -> CellCoord {
-        let column = (world_pos.x / Self::CELL_SIZE).floor() as i32;
-        let row = (world_pos.y / Self::CELL_SIZE).floor() as i32;
-        [row, column]
+        match self.layout {
+            Layout::Square => {
+                let column = (world_pos.x / Self::CELL_SIZE).floor() as i32;
+                let row = (world_pos.y / Self::CELL_SIZE).floor() as i32;
+                [row, column]
+            }
+            Layout::Hexagonal => {
+                // fractional axial coordinates of the point, relative to the
+                // hexagon centres, rounded through cube coordinates
+                let height = Self::HEX_ROW_HEIGHT * 4.0 / 3.0;
+                let row = (world_pos.y - height / 2.0) / Self::HEX_ROW_HEIGHT;
+                let col = world_pos.x / Self::CELL_SIZE - 0.5 - row / 2.0;
+                let (x, z) = (col, row);
+                let y = -x - z;
+                let (mut rx, ry, mut rz) = (x.round(), y.round(), z.round());
+                let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+                if dx > dy && dx > dz {
+                    rx = -ry - rz;
+                } else if dy <= dz {
+                    rz = -rx - ry;
+                }
+                [rz as i32, rx as i32]
+            }
+        }
     }
     fn screen2cell(&self, screen_pos: &Vec2) -> CellCoord {
         let world_pos = self.screen2world(screen_pos);
@@ -348,9 +1568,17 @@ impl Camera {
     }
     /// returns (min, max_coordd
     fn cell_bounds(&self) -> (CellCoord, CellCoord) {
-        let min = self.screen2cell(&Vec2::new(0.0, 0.0));
-        let max = self.screen2cell(&(self.screen_size * 2.0));
-        (min, max)
+        let [width, height] = (self.screen_size * 2.0).into();
+        // rows of hexagons shift sideways, so every corner can bound the columns
+        let corners = [[0.0, 0.0], [width, 0.0], [0.0, height], [width, height]]
+            .map(|corner| self.screen2cell(&corner.into()));
+        let bound = |f: fn(i32, i32) -> i32, i: usize| {
+            corners.iter().map(|cell| cell[i]).reduce(f).unwrap()
+        };
+        (
+            [bound(i32::min, 0), bound(i32::min, 1)],
+            [bound(i32::max, 0), bound(i32::max, 1)],
+        )
     }
     /// camera offset in screen space
     fn pan(&self) -> Vec2 {
@@ -401,6 +1629,38 @@ mod test_camera_transforms {
         assert_ne!(screen_pos, world_pos);
         assert_eq!(screen_pos, camera.world2screen(&world_pos));
     }
+
+    #[test]
+    /// clicks anywhere inside a hexagon land on its cell
+    fn test_hexagon_hits() {
+        let mut camera = Camera {
+            layout: Layout::Hexagonal,
+            ..Default::default()
+        };
+        camera.zoom = 0.75;
+        let width = Camera::CELL_SIZE;
+        let height = Camera::HEX_ROW_HEIGHT * 4.0 / 3.0;
+        for cell in [[0, 0], [1, 0], [-3, 7], [5, -2], [-4, -4]] {
+            let centre = camera.cell2world(&cell) + Vec2::new(width / 2.0, height / 2.0);
+            // the centre, then towards each of the six sides and corners
+            let points = [
+                [0.0, 0.0],
+                [0.45, 0.0],
+                [-0.45, 0.0],
+                [0.2, 0.35],
+                [-0.2, -0.35],
+            ]
+            .map(|[x, y]| centre + Vec2::new(x * width, y * height));
+            for point in points {
+                assert_eq!(camera.world2cell(&point), cell, "{point:?}");
+                let screen = camera.world2screen(&point);
+                assert_eq!(camera.screen2cell(&screen), cell);
+            }
+        }
+        // the next row starts half a cell further right
+        let below = camera.cell2world(&[1, 0]) - camera.cell2world(&[0, 0]);
+        assert_eq!(below.x, width / 2.0);
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -410,9 +1670,24 @@ pub enum Event {
     Step,
     Echo(String),
     ToggleCell(CellCoord),
+    /// paint cells with a state when clicked instead of toggling them, none
+    /// to go back to toggling
+    SetPaintState(Option<u8>),
     SpawnGlider(CellCoord),
+    /// stamp the library pattern called `name`, centered on `at`
+    PlacePattern {
+        name: String,
+        at: CellCoord,
+        rotation: Rotation,
+        /// flip left to right before rotating
+        mirror: bool,
+    },
     SaveWorld,
     CopyWorld,
+    SaveWorldAs(FileFormat),
+    CopyWorldAs(FileFormat),
+    /// json, rle or apgcode file contents, or a `@RULE` file with a rule
+    /// table to switch to
     LoadWorld(Vec<u8>),
     CameraPan([f32; 2]),
     CameraSize([f32; 2]),
@@ -426,6 +1701,64 @@ pub enum Event {
     /// signal camera drag stop
     AnchorDrag([f32; 2]),
     ToggleScreenCoord([f32; 2]),
+    /// rulestring such as "B3/S23" or "B36/S23"
+    SetRule(String),
+    SetEngine(Engine),
+    /// shape of the world, also settable with a rule suffix like ":T40,30"
+    SetTopology(Topology),
+    /// draw the copies of a wrapping world around it
+    ShowWrappedCopies(bool),
+    /// advance 2^n generations
+    StepPow(u32),
+    /// keep stepping the world until paused
+    Run,
+    Pause,
+    /// generations per second while running
+    SetSpeed(f32),
+    /// make the rule stochastic, or certain again with the default chances
+    SetChances(Chances),
+    /// restart the random numbers of stochastic rules from a seed
+    SetSeed(u64),
+    /// select the cells between two opposite corners
+    SelectRect(CellCoord, CellCoord),
+    /// select between two opposite corners in screen space
+    SelectScreenRect([f32; 2], [f32; 2]),
+    Deselect,
+    CopySelection,
+    CutSelection,
+    /// stamp the copied cells with their top left corner at the cell
+    Paste(CellCoord),
+    /// kill the selected cells
+    ClearSelection,
+    /// fill the rectangle between two opposite corners with a random soup,
+    /// cells alive with a chance of `density`
+    RandomFill {
+        rect: [CellCoord; 2],
+        density: f32,
+        seed: u64,
+        symmetry: Symmetry,
+    },
+    /// turn the selection clockwise around its top left corner
+    RotateSelection(Rotation),
+    /// mirror the selection top to bottom
+    FlipSelectionRows,
+    /// mirror the selection left to right
+    FlipSelectionColumns,
+    /// revert the last edit, load or run of steps
+    Undo,
+    Redo,
+    /// go back one generation of the current run
+    StepBack,
+    /// step and run reversible rules backwards, computing earlier generations
+    RunBackwards(bool),
+    /// stop running once the world repeats
+    PauseOnCycle(bool),
+    /// count the objects in the world by their apgcodes, cells at most this
+    /// many dead cells apart belonging to the same object
+    Census(u32),
+    /// timer wake up of the run loop
+    #[serde(skip)]
+    RunFrame(u64),
 }
 
 #[cfg_attr(feature = "typegen", derive(crux_core::macros::Export))]
@@ -436,6 +1769,7 @@ pub struct Capabilites {
     /// capable of asking shell to preform http requests
     alert: Alert<Event>,
     pub file_io: FileIO<Event>,
+    pub timer: Timer<Event>,
 }
 
 // #[derive(Serialize, Deserialize, Clone)]
@@ -447,11 +1781,63 @@ pub struct Capabilites {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ViewModel {
     pub cell_coords: Vec<[f32; 2]>,
+    /// state of each of `cell_coords`, 1 is alive and higher ones are decaying
+    pub cell_states: Vec<u8>,
     /// camera position in screen scale
     pub camera_pan: [f32; 2],
     pub cell_size: f32,
     pub modx: f32,
     pub mody: f32,
+    /// current rulestring, e.g. "B3/S23"
+    pub rule: String,
+    pub engine: Engine,
+    pub running: bool,
+    /// generations per second while running
+    pub speed: f32,
+    /// generations stepped since the world was loaded
+    pub generation: u64,
+    pub population: u64,
+    /// top left and bottom right live cells as [row, column]
+    pub bounding_box: Option<[[i32; 2]; 2]>,
+    /// cells born by the last step
+    pub births: u64,
+    /// cells that died in the last step
+    pub deaths: u64,
+    /// population after each recent step, oldest first
+    pub population_history: Vec<u64>,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub can_step_back: bool,
+    /// top left and bottom right selected cells as [row, column]
+    pub selection: Option<[[i32; 2]; 2]>,
+    /// top left and bottom right corners of the selection in screen space
+    pub selection_screen: Option<[[f32; 2]; 2]>,
+    pub can_paste: bool,
+    pub topology: Topology,
+    pub show_wrapped: bool,
+    /// top left and bottom right corners of a finite world in screen space
+    pub world_box: Option<[[f32; 2]; 2]>,
+    /// squares or hexagons, `cell_coords` are the top left of their boxes
+    pub layout: Layout,
+    /// cell states of the rule, dead included
+    pub states: u8,
+    /// colour of each state as [red, green, blue] when the rule sets them,
+    /// empty for the default colours
+    pub palette: Vec<[u8; 3]>,
+    /// state clicked cells are painted with, none when they are toggled
+    pub paint_state: Option<u8>,
+    /// the rule can run backwards
+    pub reversible: bool,
+    /// steps of a reversible rule go back in time
+    pub backwards: bool,
+    pub chances: Chances,
+    /// seed of the random numbers of stochastic rules
+    pub seed: u64,
+    /// cycle the run settled into, see [Event::PauseOnCycle]
+    pub cycle: Option<Cycle>,
+    pub pause_on_cycle: bool,
+    /// objects counted by the last [Event::Census]
+    pub census: Option<Census>,
 }
 impl Display for ViewModel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -487,36 +1873,382 @@ impl crux_core::App for App {
             Event::Render => {
                 caps.render.render();
             }
+            Event::LoadWorld(data) if table::is_rule_file(&data) => {
+                match model.tables.load(&String::from_utf8_lossy(&data)) {
+                    Ok(table) => {
+                        let topology = model.rule.topology();
+                        model.set_rule(Rule::from_table(table).with_topology(topology));
+                        if model.engine_unsupported() {
+                            caps.alert.warning(model.engine_warning().to_string());
+                        }
+                        caps.render.render();
+                    }
+                    Err(err) => caps.alert.error(format!("Failed to load rule: {err}")),
+                }
+            }
             Event::LoadWorld(data) => {
-                let coords: CellVector = serde_json::from_slice(data.as_slice()).unwrap();
-                model.life.clear();
-                model.life.add_cells(&coords);
-                caps.render.render();
+                // patterns saved in a loaded rule carry its rule file
+                let text = String::from_utf8_lossy(&data);
+                if let Some(rule_file) = table::trailing_rule_file(&text) {
+                    if let Err(err) = model.tables.load(rule_file) {
+                        caps.alert.error(format!("Failed to load rule: {err}"));
+                        return;
+                    }
+                }
+                match format::decode(&data, &model.tables) {
+                    Ok(pattern) => {
+                        model.history.push(Entry::Replace(model.snapshot()));
+                        let cells = pattern.cell_states();
+                        model.switch_rule(pattern.rule.unwrap_or_else(|| model.rule.clone()));
+                        let states = model.rule.states();
+                        let unknown = model.life.load_states(&cells, states);
+                        model.life.fit(model.rule.topology());
+                        model.comments = pattern.comments;
+                        model.stats.reset();
+                        if unknown > 0 {
+                            let rule = &model.rule;
+                            caps.alert.warning(format!(
+                                "Left out {unknown} cells in states {rule} does not have"
+                            ));
+                        }
+                        caps.render.render();
+                    }
+                    Err(err) => caps.alert.error(format!("Failed to load world: {err}")),
+                }
             }
             Event::SaveWorld => {
-                caps.file_io.save(model);
+                self.update(Event::SaveWorldAs(FileFormat::Json), model, caps);
             }
             Event::CopyWorld => {
-                caps.file_io.copy(model);
+                self.update(Event::CopyWorldAs(FileFormat::Json), model, caps);
+            }
+            Event::SaveWorldAs(format) => {
+                let file_name = format!("exported_life.{}", format.extension());
+                caps.file_io.save(file_name, model.export(format));
+            }
+            Event::CopyWorldAs(format) => {
+                caps.file_io.copy(model.export(format));
             }
             Event::Echo(msg) => {
                 caps.alert.info(msg);
             }
             Event::ToggleCell(coord) => {
-                model.life.toggle_cell(coord);
+                model.toggle(coord);
                 caps.render.render();
             }
+            Event::SetPaintState(paint) => match paint {
+                Some(state) if state >= model.rule.states() => caps
+                    .alert
+                    .error(format!("{} has no state {state}", model.rule)),
+                paint => {
+                    model.paint = paint;
+                    caps.render.render();
+                }
+            },
             Event::ToggleScreenCoord(screen_pos) => {
                 let world_pos = model.camera.screen2world(&screen_pos.into());
                 let coord = model.camera.world2cell(&world_pos);
-                model.life.toggle_cell(coord);
+                model.toggle(coord);
                 caps.render.render();
             }
             Event::Step => {
-                model.life.tick();
+                model.runner.running = false;
+                if !model.advance(0) {
+                    caps.alert.error(WORLD_OUT_OF_BOUNDS.to_string());
+                }
+                caps.render.render();
+            }
+            Event::StepPow(pow) => {
+                model.runner.running = false;
+                let max = model.max_step_pow();
+                if pow > max {
+                    caps.alert.warning(format!(
+                        "Jumps are limited to 2^{max} generations with this engine"
+                    ));
+                }
+                if !model.advance(pow.min(max)) {
+                    caps.alert.error(WORLD_OUT_OF_BOUNDS.to_string());
+                }
+                caps.render.render();
+            }
+            Event::SelectRect(from, to) => {
+                model.selection = Some(Rect::from_corners(from, to));
+                caps.render.render();
+            }
+            Event::SelectScreenRect(from, to) => {
+                let from = model.camera.screen2cell(&from.into());
+                let to = model.camera.screen2cell(&to.into());
+                self.update(Event::SelectRect(from, to), model, caps);
+            }
+            Event::Deselect => {
+                model.selection = None;
+                caps.render.render();
+            }
+            Event::CopySelection => {
+                if let Some(rect) = model.selection {
+                    model.clipboard = Some((selection::copy(&model.life, &rect), rect));
+                }
+                caps.render.render();
+            }
+            Event::CutSelection => {
+                self.update(Event::CopySelection, model, caps);
+                self.update(Event::ClearSelection, model, caps);
+            }
+            Event::Paste(at) => {
+                let Some((cells, copied)) = &model.clipboard else {
+                    caps.alert
+                        .info("Nothing to paste, copy a selection first".to_string());
+                    return;
+                };
+                let mut cells = cells.clone();
+                let Some(rect) = selection::paste(&mut cells, copied, at) else {
+                    caps.alert.error(OUT_OF_BOUNDS.to_string());
+                    return;
+                };
+                model.stamp(cells.cell_states());
+                model.selection = Some(rect);
+                caps.render.render();
+            }
+            Event::ClearSelection => {
+                model.transform_selection(|cells, rect| {
+                    cells.clear();
+                    Some(*rect)
+                });
+                caps.render.render();
+            }
+            Event::RandomFill {
+                rect: [from, to],
+                density,
+                seed,
+                symmetry,
+            } => {
+                let rect = Rect::from_corners(from, to);
+                let [rows, cols] = rect.extent().map(|n| u64::from(n) + 1);
+                if !(0.0..=1.0).contains(&density) {
+                    caps.alert
+                        .error("Density must be between 0 and 1".to_string());
+                    return;
+                }
+                if rows.saturating_mul(cols) > soup::MAX_CELLS {
+                    let max = soup::MAX_CELLS;
+                    caps.alert
+                        .error(format!("Soups are limited to {max} cells"));
+                    return;
+                }
+                model.edit(soup::fill(&rect, density, seed, symmetry));
+                caps.render.render();
+            }
+            Event::RotateSelection(rotation) => {
+                if !model
+                    .transform_selection(|cells, rect| selection::rotate(cells, rect, rotation))
+                {
+                    caps.alert.error(OUT_OF_BOUNDS.to_string());
+                    return;
+                }
+                caps.render.render();
+            }
+            Event::FlipSelectionRows => {
+                model.transform_selection(|cells, rect| {
+                    selection::flip(cells, rect, true);
+                    Some(*rect)
+                });
                 caps.render.render();
             }
-            Event::SpawnGlider(_coord) => todo!(),
+            Event::FlipSelectionColumns => {
+                model.transform_selection(|cells, rect| {
+                    selection::flip(cells, rect, false);
+                    Some(*rect)
+                });
+                caps.render.render();
+            }
+            Event::Undo => {
+                model.runner.running = false;
+                if let Some(entry) = model.history.undo() {
+                    let redo = model.revert(entry);
+                    model.history.push_redo(redo);
+                }
+                caps.render.render();
+            }
+            Event::Redo => {
+                model.runner.running = false;
+                if let Some(entry) = model.history.redo() {
+                    let undo = model.revert(entry);
+                    model.history.push_undo(undo);
+                }
+                caps.render.render();
+            }
+            Event::StepBack => {
+                model.runner.running = false;
+                if !model.step_back() {
+                    caps.alert
+                        .info("No earlier generation to step back to".to_string());
+                }
+                caps.render.render();
+            }
+            Event::Run => {
+                if !model.runner.running {
+                    model.runner.running = true;
+                    let frame = model.runner.next_frame();
+                    let millis = model.runner.frame_millis;
+                    caps.timer.notify_after(millis, Event::RunFrame(frame));
+                }
+                caps.render.render();
+            }
+            Event::RunBackwards(backwards) => {
+                model.runner.backwards = backwards;
+                if backwards && model.rule.margolus().is_none() {
+                    caps.alert.info(
+                        "Only reversible block rules run backwards, use undo for others"
+                            .to_string(),
+                    );
+                }
+                caps.render.render();
+            }
+            Event::Census(gap) => {
+                let rule = &model.rule;
+                if !census::runs(rule) {
+                    caps.alert.info(
+                        "Censuses need two state rules where only the cells decide \
+                        the next generation"
+                            .to_string(),
+                    );
+                    return;
+                }
+                if gap > census::MAX_GAP {
+                    let max = census::MAX_GAP;
+                    caps.alert
+                        .warning(format!("Gaps are limited to {max} cells"));
+                }
+                let cells: CellVector = model.life.live_cells().copied().collect();
+                let generation = model.stats.generation;
+                model.census = Some(census::take(&cells, rule, gap, generation));
+                caps.render.render();
+            }
+            Event::PauseOnCycle(pause) => {
+                model.runner.pause_on_cycle = pause;
+                caps.render.render();
+            }
+            Event::Pause => {
+                model.runner.running = false;
+                caps.render.render();
+            }
+            Event::SetSpeed(speed) => {
+                model.runner.set_speed(speed);
+                caps.render.render();
+            }
+            Event::RunFrame(frame) => {
+                if !model.runner.running || frame != model.runner.frame {
+                    return;
+                }
+                let settled = model.cycle().is_some();
+                // hashlife and tiles take the frame in one go, building the
+                // cells of `life` once
+                let generations = u64::from(model.runner.generations_per_frame);
+                let (steps, generations) = match model.engine_runs() {
+                    true => (1, generations),
+                    false => (generations, 1),
+                };
+                for _ in 0..steps {
+                    if !model.advance_by(generations) {
+                        caps.alert.error(WORLD_OUT_OF_BOUNDS.to_string());
+                        caps.render.render();
+                        return;
+                    }
+                    if model.runner.pause_on_cycle && !settled {
+                        if let Some(cycle) = model.cycle() {
+                            model.runner.running = false;
+                            let Cycle {
+                                generation, period, ..
+                            } = cycle;
+                            caps.alert.info(format!(
+                                "Stabilized at generation {generation} with period {period}"
+                            ));
+                            caps.render.render();
+                            return;
+                        }
+                    }
+                }
+                let millis = model.runner.frame_millis;
+                caps.timer.notify_after(millis, Event::RunFrame(frame));
+                caps.render.render();
+            }
+            Event::SetChances(chances) => {
+                if !chances.is_valid() {
+                    caps.alert
+                        .error("Chances must be between 0 and 1".to_string());
+                    return;
+                }
+                // the run so far steps back with the chances it was run with
+                if chances != model.chances {
+                    model.history.push(Entry::Replace(model.snapshot()));
+                }
+                model.chances = chances;
+                if model.engine_unsupported() {
+                    caps.alert.warning(model.engine_warning().to_string());
+                }
+                caps.render.render();
+            }
+            Event::SetSeed(seed) => {
+                model.history.push(Entry::Replace(model.snapshot()));
+                model.seed = seed;
+                model.random = Random::new(seed);
+                caps.render.render();
+            }
+            Event::SetEngine(engine) => {
+                model.engine = engine;
+                model.engine_world = None;
+                if model.engine_unsupported() {
+                    caps.alert.warning(model.engine_warning().to_string());
+                }
+                caps.render.render();
+            }
+            Event::SetRule(rulestring) => match Rule::parse(&rulestring, &model.tables) {
+                Ok(rule) => {
+                    model.set_rule(rule);
+                    if model.engine_unsupported() {
+                        caps.alert.warning(model.engine_warning().to_string());
+                    }
+                    caps.render.render();
+                }
+                Err(err) => caps.alert.error(format!("Invalid rule: {err}")),
+            },
+            Event::SetTopology(topology) => {
+                if topology.is_valid() {
+                    let rulestring = model.rule.clone().with_topology(topology).to_string();
+                    self.update(Event::SetRule(rulestring), model, caps);
+                } else {
+                    let max = topology::MAX_SIZE;
+                    caps.alert
+                        .error(format!("World sizes must be between 1 and {max}"));
+                }
+            }
+            Event::ShowWrappedCopies(show) => {
+                model.show_wrapped = show;
+                caps.render.render();
+            }
+            Event::SpawnGlider(at) => {
+                let event = Event::PlacePattern {
+                    name: "Glider".to_string(),
+                    at,
+                    rotation: Rotation::R0,
+                    mirror: false,
+                };
+                self.update(event, model, caps);
+            }
+            Event::PlacePattern {
+                name,
+                at,
+                rotation,
+                mirror,
+            } => match library::find(&name) {
+                Some(pattern) => {
+                    model.stamp(library::orient(pattern, at, rotation, mirror));
+                    caps.render.render();
+                }
+                None => caps
+                    .alert
+                    .error(format!("No pattern named {name:?} in the library")),
+            },
             Event::CameraSize(size) => {
                 let new_size = size.map(|e| e / 2.0).into();
                 let world_size_diff = (new_size - model.camera.screen_size) / model.camera.zoom;
@@ -566,24 +2298,936 @@ impl crux_core::App for App {
 
     fn view(&self, model: &Self::Model) -> Self::ViewModel {
         let (min_cell, max_cell) = model.camera.cell_bounds();
-        let cell_coords = model
+        let (cell_coords, cell_states) = model
             .life
             .state
             .iter()
-            .filter(|cell| cell[0] >= min_cell[0])
-            .filter(|cell| cell[1] >= min_cell[1])
-            .filter(|cell| cell[0] <= max_cell[0])
-            .filter(|cell| cell[1] <= max_cell[1])
-            .map(|cell| model.camera.cell2creen(cell).into())
-            .collect();
+            .filter(|(cell, _)| cell[0] >= min_cell[0])
+            .filter(|(cell, _)| cell[1] >= min_cell[1])
+            .filter(|(cell, _)| cell[0] <= max_cell[0])
+            .filter(|(cell, _)| cell[1] <= max_cell[1])
+            .map(|(cell, state)| (*cell, *state))
+            .chain(model.wrapped_copies(min_cell, max_cell))
+            .map(|(cell, state)| -> ([f32; 2], u8) {
+                (model.camera.cell2creen(&cell).into(), state)
+            })
+            .unzip();
         let grid_offset = model.camera.pan().into();
         let [modx, mody] = model.camera.grid_mod().into();
         ViewModel {
             cell_coords,
+            cell_states,
             cell_size: model.camera.cell_size(),
             camera_pan: grid_offset,
             modx,
             mody,
+            rule: model.rule.to_string(),
+            engine: model.engine,
+            running: model.runner.running,
+            speed: model.runner.speed,
+            generation: model.stats.generation,
+            population: model.life.state.len() as u64,
+            bounding_box: model.life.bounds(),
+            births: model.stats.births,
+            deaths: model.stats.deaths,
+            population_history: model.stats.history.iter().copied().collect(),
+            can_undo: model.history.can_undo(),
+            can_redo: model.history.can_redo(),
+            can_step_back: model.history.can_step_back(model.stats.generation),
+            selection: model.selection.map(|rect| rect.corners()),
+            selection_screen: model.selection.map(|rect| {
+                let [row, col] = rect.max;
+                [
+                    model.camera.cell2creen(&rect.min).into(),
+                    model.camera.cell2creen(&[row + 1, col + 1]).into(),
+                ]
+            }),
+            can_paste: model.clipboard.is_some(),
+            topology: model.rule.topology(),
+            show_wrapped: model.show_wrapped,
+            world_box: model.rule.topology().corners().map(|[min, [row, col]]| {
+                [
+                    model.camera.cell2creen(&min).into(),
+                    model.camera.cell2creen(&[row + 1, col + 1]).into(),
+                ]
+            }),
+            layout: model.camera.layout,
+            states: model.rule.states(),
+            palette: model
+                .rule
+                .table()
+                .map_or_else(Vec::new, |table| table.colors().to_vec()),
+            paint_state: model.paint,
+            reversible: model.rule.margolus().is_some(),
+            backwards: model.runner.backwards,
+            chances: model.chances,
+            seed: model.seed,
+            cycle: model.cycle(),
+            pause_on_cycle: model.runner.pause_on_cycle,
+            census: model.census.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_app {
+    use super::*;
+    use crux_core::testing::{AppTester, Update};
+    use crux_core::Request;
+
+    /// app and a model of `life` with the default rule and settings
+    fn start(life: Life) -> (AppTester<App, Effect>, Model) {
+        let model = Model {
+            life,
+            ..Default::default()
+        };
+        (AppTester::default(), model)
+    }
+
+    /// the update raised an alert
+    fn alerts(update: &Update<Effect, Event>) -> bool {
+        update
+            .effects()
+            .any(|effect| matches!(effect, Effect::Alert(_)))
+    }
+
+    /// sends `events` one after the other, true when any of them raised an
+    /// alert
+    fn send(
+        app: &AppTester<App, Effect>,
+        model: &mut Model,
+        events: impl IntoIterator<Item = Event>,
+    ) -> bool {
+        let mut alerted = false;
+        for event in events {
+            alerted |= alerts(&app.update(event, model));
+        }
+        alerted
+    }
+
+    #[test]
+    /// a broken file is reported and leaves the world as it was
+    fn test_load_error() {
+        let (app, mut model) = start(Life::default());
+        let before = model.life.clone();
+        let update = app.update(Event::LoadWorld(b"[[0, 1], [2".to_vec()), &mut model);
+        let Effect::Alert(request) = update.expect_one_effect() else {
+            panic!("expected an alert");
+        };
+        assert!(matches!(request.operation, AlertOpereation::Error(_)));
+        assert_eq!(model.life, before);
+    }
+
+    fn timer_request(update: Update<Effect, Event>) -> Option<Request<TimerOperation>> {
+        update.into_effects().find_map(|effect| match effect {
+            Effect::Timer(request) => Some(request),
+            _ => None,
+        })
+    }
+
+    #[test]
+    /// each wake up steps the world and asks for the next one, until paused
+    fn test_run_loop() {
+        let (app, mut model) = start(Life::blinker());
+        send(&app, &mut model, [Event::SetSpeed(10.0)]);
+
+        let mut timer = timer_request(app.update(Event::Run, &mut model)).unwrap();
+        assert_eq!(timer.operation.millis, 100);
+        for generation in 1..=3 {
+            let update = app.resolve(&mut timer, ()).unwrap();
+            let update = app.update(update.expect_one_event(), &mut model);
+            assert_eq!(model.life != Life::blinker(), generation % 2 == 1);
+            timer = timer_request(update).unwrap();
         }
+
+        send(&app, &mut model, [Event::Pause]);
+        let update = app.resolve(&mut timer, ()).unwrap();
+        let update = app.update(update.expect_one_event(), &mut model);
+        assert!(update.effects().next().is_none());
+    }
+
+    #[test]
+    /// the glider is found moving after a period, edits start over and a
+    /// run pausing on cycles stops once the blinker repeats
+    fn test_cycle() {
+        let (app, mut model) = start(Life::glider());
+        for _ in 0..3 {
+            send(&app, &mut model, [Event::Step]);
+            assert_eq!(app.view(&model).cycle, None);
+        }
+        send(&app, &mut model, [Event::Step]);
+        let glider = Cycle {
+            generation: 0,
+            period: 4,
+            displacement: [-1, 1],
+        };
+        assert_eq!(app.view(&model).cycle, Some(glider));
+        send(&app, &mut model, [Event::ToggleCell([20, 20])]);
+        assert_eq!(app.view(&model).cycle, None);
+
+        let (app, mut model) = start(Life::blinker());
+        send(
+            &app,
+            &mut model,
+            [Event::SetSpeed(10.0), Event::PauseOnCycle(true)],
+        );
+        let mut timer = timer_request(app.update(Event::Run, &mut model)).unwrap();
+        let update = app.resolve(&mut timer, ()).unwrap();
+        let update = app.update(update.expect_one_event(), &mut model);
+        timer = timer_request(update).unwrap();
+        let update = app.resolve(&mut timer, ()).unwrap();
+        let update = app.update(update.expect_one_event(), &mut model);
+        assert!(alerts(&update));
+        assert!(timer_request(update).is_none());
+        let view = app.view(&model);
+        assert!(!view.running);
+        assert_eq!(view.cycle.map(|cycle| cycle.period), Some(2));
+    }
+
+    #[test]
+    fn test_stats() {
+        let (app, mut model) = start(Life::blinker());
+        let view = app.view(&model);
+        assert_eq!(view.generation, 0);
+        assert_eq!(view.population, 3);
+        assert_eq!(view.bounding_box, Some([[0, -1], [0, 1]]));
+
+        send(&app, &mut model, [Event::Step]);
+        let view = app.view(&model);
+        assert_eq!(view.generation, 1);
+        assert_eq!((view.births, view.deaths), (2, 2));
+        assert_eq!(view.bounding_box, Some([[-1, 0], [1, 0]]));
+
+        // a jump compares the worlds at both ends
+        send(&app, &mut model, [Event::StepPow(3)]);
+        let view = app.view(&model);
+        assert_eq!(view.generation, 9);
+        assert_eq!((view.births, view.deaths), (0, 0));
+        assert_eq!(view.population_history, vec![3, 3]);
+
+        send(&app, &mut model, [Event::LoadWorld(b"[]".to_vec())]);
+        let view = app.view(&model);
+        assert_eq!(view.generation, 0);
+        assert_eq!(view.population, 0);
+        assert_eq!(view.bounding_box, None);
+        assert!(view.population_history.is_empty());
+
+        for _ in 0..Stats::HISTORY_LENGTH + 1 {
+            model.advance(0);
+        }
+        assert_eq!(model.stats.history.len(), Stats::HISTORY_LENGTH);
+    }
+
+    #[test]
+    /// an import or a run can be undone back to a hand built pattern
+    fn test_undo_redo() {
+        let (app, mut model) = start(Life::empty());
+        let toggles = Life::glider().state_as_list().into_iter();
+        send(&app, &mut model, toggles.map(Event::ToggleCell));
+        let drawn = model.life.clone();
+
+        send(
+            &app,
+            &mut model,
+            [Event::LoadWorld(b"[[5, 5]]".to_vec()), Event::Undo],
+        );
+        assert_eq!(model.life, drawn);
+        send(&app, &mut model, [Event::Redo]);
+        assert_eq!(model.life.state_as_list(), vec![[5, 5]]);
+        send(&app, &mut model, [Event::Undo]);
+
+        let mut generations = vec![model.life.clone()];
+        for _ in 0..100 {
+            send(&app, &mut model, [Event::Step]);
+            generations.push(model.life.clone());
+        }
+        send(&app, &mut model, [Event::StepPow(4)]);
+        assert_eq!(model.stats.generation, 116);
+        send(&app, &mut model, (0..16).map(|_| Event::StepBack));
+        assert_eq!(model.stats.generation, 100);
+        assert_eq!(model.life, generations[100]);
+        for generation in (60..100).rev() {
+            send(&app, &mut model, [Event::StepBack]);
+            assert_eq!(model.life, generations[generation]);
+        }
+        assert!(app.view(&model).can_step_back);
+
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life, drawn);
+        assert_eq!(model.stats.generation, 0);
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life.state.len(), 4);
+        let view = app.view(&model);
+        assert!(view.can_undo && view.can_redo && !view.can_step_back);
+    }
+
+    #[test]
+    /// a rule change ends the run, stepping back stops at it and undo brings
+    /// back the old rule to step back with
+    fn test_rule_change_mid_run() {
+        let rpentomino = &library::find("R-pentomino").unwrap().cells;
+        let (app, mut model) = start(Life::new(rpentomino));
+        let mut conway = vec![model.life.clone()];
+        for _ in 0..5 {
+            send(&app, &mut model, [Event::Step]);
+            conway.push(model.life.clone());
+        }
+        send(&app, &mut model, [Event::SetRule("B36/S23".to_string())]);
+        let mut highlife = vec![model.life.clone()];
+        for _ in 0..3 {
+            send(&app, &mut model, [Event::Step]);
+            highlife.push(model.life.clone());
+        }
+        let mut stepped = conway[5].clone();
+        (0..3).for_each(|_| stepped.tick(&Rule::default()));
+        assert_ne!(stepped.state, highlife[3].state);
+
+        for generation in (0..3).rev() {
+            send(&app, &mut model, [Event::StepBack]);
+            assert_eq!(model.life, highlife[generation]);
+        }
+        assert_eq!(app.view(&model).rule, "B36/S23");
+        assert!(!app.view(&model).can_step_back);
+
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(app.view(&model).rule, "B3/S23");
+        assert_eq!(model.stats.generation, 5);
+        send(&app, &mut model, [Event::StepBack]);
+        assert_eq!(model.life, conway[4]);
+        send(&app, &mut model, [Event::Redo, Event::Step]);
+        assert_eq!(model.life, highlife[1]);
+    }
+
+    #[test]
+    fn test_place_pattern() {
+        let (app, mut model) = start(Life::empty());
+        send(&app, &mut model, [Event::SpawnGlider([0, 0])]);
+        assert_eq!(model.life.state.len(), 5);
+        // overlapping cells stay alive and only the new ones are undone
+        let place = Event::PlacePattern {
+            name: "Block".to_string(),
+            at: [0, 0],
+            rotation: Rotation::R180,
+            mirror: true,
+        };
+        send(&app, &mut model, [place]);
+        assert_eq!(model.life.state.len(), 7);
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life.state.len(), 5);
+
+        let unknown = Event::PlacePattern {
+            name: "Nothing".to_string(),
+            at: [0, 0],
+            rotation: Rotation::R0,
+            mirror: false,
+        };
+        let Effect::Alert(request) = app.update(unknown, &mut model).expect_one_effect() else {
+            panic!("expected an alert");
+        };
+        assert!(matches!(request.operation, AlertOpereation::Error(_)));
+        assert!(catalog().iter().any(|p| p.name == "Glider"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let (app, mut model) = start(Life::glider());
+        let glider = model.life.clone();
+        send(&app, &mut model, [Event::SelectRect([1, 2], [-1, 0])]);
+        assert_eq!(app.view(&model).selection, Some([[-1, 0], [1, 2]]));
+        let update = app.update(Event::CopySelection, &mut model);
+        assert!(matches!(update.expect_one_effect(), Effect::Render(_)));
+        assert!(app.view(&model).can_paste);
+        send(&app, &mut model, [Event::CutSelection]);
+        assert!(model.life.state.is_empty());
+        assert!(app.view(&model).can_paste);
+
+        send(&app, &mut model, [Event::Paste([9, 10])]);
+        let mut moved = glider.clone();
+        moved.translate(&[10, 10]);
+        assert_eq!(model.life.state, moved.state);
+        assert_eq!(app.view(&model).selection, Some([[9, 10], [11, 12]]));
+
+        // four quarter turns and two flips each way leave the glider as it was
+        let turns = (0..4).map(|_| Event::RotateSelection(Rotation::R90));
+        send(&app, &mut model, turns);
+        let flips = [Event::FlipSelectionRows, Event::FlipSelectionColumns];
+        send(&app, &mut model, flips.clone().into_iter().chain(flips));
+        assert_eq!(model.life.state, moved.state);
+        send(&app, &mut model, [Event::RotateSelection(Rotation::R180)]);
+        assert_ne!(model.life.state, moved.state);
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life.state, moved.state);
+
+        // selections never move past the largest coordinates
+        let edge = Event::Paste([i32::MAX - 1, 0]);
+        assert!(send(&app, &mut model, [Event::CopySelection, edge]));
+        assert_eq!(model.life.state, moved.state);
+        let wide = Event::SelectRect([i32::MIN, 30], [i32::MAX, 31]);
+        let toggle = Event::ToggleCell([i32::MIN, 30]);
+        assert!(!send(
+            &app,
+            &mut model,
+            [wide, toggle, Event::FlipSelectionRows]
+        ));
+        assert!(model.life.is_alive(&[i32::MAX, 30]));
+        assert!(send(
+            &app,
+            &mut model,
+            [Event::RotateSelection(Rotation::R90)]
+        ));
+        assert!(model.life.is_alive(&[i32::MAX, 30]));
+        let glider = Event::SelectRect([9, 10], [11, 12]);
+        send(&app, &mut model, [Event::Undo, Event::Undo, glider]);
+        assert_eq!(model.life.state, moved.state);
+
+        send(&app, &mut model, [Event::ClearSelection]);
+        assert!(model.life.state.is_empty());
+        send(&app, &mut model, [Event::Deselect]);
+        assert_eq!(app.view(&model).selection_screen, None);
+    }
+
+    #[test]
+    /// decaying cells are shown, edited, undone and saved with their states
+    fn test_generations() {
+        let (app, mut model) = start(Life::default());
+        let rle = b"x = 3, y = 1, rule = B2/S/C3\nAB!".to_vec();
+        send(&app, &mut model, [Event::LoadWorld(rle)]);
+        let view = app.view(&model);
+        assert_eq!(view.rule, "B2/S/C3");
+        let mut states = view.cell_states.clone();
+        states.sort();
+        assert_eq!(states, vec![ALIVE, 2]);
+        assert_eq!(view.population, 2);
+
+        send(&app, &mut model, [Event::ToggleCell([0, 0])]);
+        assert_eq!(model.life.state_of(&[0, 0]), 0);
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life.state_of(&[0, 0]), 2);
+
+        let saved = model.export(FileFormat::Json);
+        let mut loaded = Model {
+            rule: model.rule.clone(),
+            ..Default::default()
+        };
+        send(&app, &mut loaded, [Event::LoadWorld(saved)]);
+        assert_eq!(loaded.life.state, model.life.state);
+
+        assert!(send(&app, &mut model, [Event::SetEngine(Engine::HashLife)]));
+        send(&app, &mut model, [Event::Step]);
+        let view = app.view(&model);
+        assert_eq!((view.births, view.deaths), (0, 1));
+        assert_eq!(model.life.state_of(&[0, -1]), 2);
+        assert_eq!(model.life.state_of(&[0, 0]), 0);
+    }
+
+    #[test]
+    /// cells in states the loaded rule does not have are left out with a
+    /// warning, and the paint state is checked against the new rule
+    fn test_load_unknown_states() {
+        let (app, mut model) = start(Life::default());
+        model.rule = "B2/S/C4".parse().unwrap();
+        model.paint = Some(3);
+        let load = Event::LoadWorld(b"[[0,0,255],[0,1]]".to_vec());
+        assert!(send(&app, &mut model, [load]));
+        assert_eq!(model.life.cell_states(), [([0, 1], ALIVE)]);
+        assert_eq!(app.view(&model).paint_state, Some(3));
+        send(&app, &mut model, [Event::Step]);
+
+        let rle = b"x = 2, y = 1, rule = B3/S23\nyOA!".to_vec();
+        assert!(send(&app, &mut model, [Event::LoadWorld(rle)]));
+        assert_eq!(model.life.state.len(), 1);
+        assert_eq!(app.view(&model).paint_state, None);
+        send(&app, &mut model, [Event::Step]);
+    }
+
+    #[test]
+    /// wires are painted in the states of Wireworld and rule files switch rules
+    fn test_paint_states() {
+        let (app, mut model) = start(Life::empty());
+        send(
+            &app,
+            &mut model,
+            [
+                Event::SetRule("Wireworld".to_string()),
+                Event::SetPaintState(Some(3)),
+            ],
+        );
+        send(
+            &app,
+            &mut model,
+            (0..4).map(|col| Event::ToggleCell([0, col])),
+        );
+        send(
+            &app,
+            &mut model,
+            [Event::SetPaintState(Some(1)), Event::ToggleCell([0, 0])],
+        );
+        let view = app.view(&model);
+        assert_eq!((view.rule.as_str(), view.states), ("Wireworld", 4));
+        assert_eq!(view.paint_state, Some(1));
+        assert_eq!(view.palette.len(), 4);
+        send(&app, &mut model, [Event::Step]);
+        let states: Vec<u8> = (0..4).map(|col| model.life.state_of(&[0, col])).collect();
+        assert_eq!(states, [2, 1, 3, 3]);
+
+        let update = app.update(Event::SetPaintState(Some(4)), &mut model);
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        send(
+            &app,
+            &mut model,
+            [Event::SetPaintState(None), Event::ToggleCell([0, 3])],
+        );
+        assert_eq!(model.life.state_of(&[0, 3]), 0);
+
+        let rule = b"@RULE Decay\n@TABLE\nn_states:3\nsymmetries:none\n1000000002\n2000000000\n";
+        send(
+            &app,
+            &mut model,
+            [
+                Event::SetPaintState(Some(2)),
+                Event::LoadWorld(rule.to_vec()),
+            ],
+        );
+        let view = app.view(&model);
+        assert_eq!((view.rule.as_str(), view.states), ("Decay", 3));
+        assert_eq!(view.paint_state, Some(2));
+        assert!(view.palette.is_empty());
+        let update = app.update(Event::LoadWorld(b"@RULE Decay\n".to_vec()), &mut model);
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        send(&app, &mut model, [Event::SetRule("B3/S23".to_string())]);
+        assert_eq!(app.view(&model).paint_state, None);
+    }
+
+    #[test]
+    /// worlds keep the tables they load to themselves, and rle saved in one
+    /// brings it along
+    fn test_rule_tables() {
+        let (app, mut model) = start(Life::empty());
+        let rule = b"@RULE Decay\n@TABLE\nn_states:3\nsymmetries:none\n1000000002\n2000000000\n";
+        send(
+            &app,
+            &mut model,
+            [
+                Event::LoadWorld(rule.to_vec()),
+                Event::SetPaintState(Some(2)),
+                Event::ToggleCell([0, 0]),
+            ],
+        );
+        let saved = model.export(FileFormat::Rle);
+        assert!(String::from_utf8_lossy(&saved).contains("!\n@RULE Decay\n"));
+
+        let mut fresh = Model::default();
+        let update = app.update(Event::SetRule("Decay".to_string()), &mut fresh);
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        send(&app, &mut fresh, [Event::LoadWorld(saved)]);
+        let view = app.view(&fresh);
+        assert_eq!((view.rule.as_str(), view.states), ("Decay", 3));
+        assert_eq!(fresh.life.cell_states(), [([0, 0], 2)]);
+
+        // built in tables are known everywhere and not written out
+        send(&app, &mut fresh, [Event::SetRule("Wireworld".to_string())]);
+        let saved = String::from_utf8(fresh.export(FileFormat::Rle)).unwrap();
+        assert!(saved.ends_with("!\n"), "{saved}");
+    }
+
+    #[test]
+    /// one dimensional rules append rows to the world like any other step
+    fn test_wolfram() {
+        let (app, mut model) = start(Life::empty());
+        send(
+            &app,
+            &mut model,
+            [
+                Event::ToggleCell([0, 0]),
+                Event::SetRule("W90".to_string()),
+                Event::StepPow(2),
+            ],
+        );
+        let view = app.view(&model);
+        assert_eq!((view.generation, view.population), (4, 1 + 2 + 2 + 4 + 2));
+        assert_eq!(view.bounding_box, Some([[0, -4], [4, 4]]));
+        let rle = String::from_utf8(model.export(FileFormat::Rle)).unwrap();
+        assert!(rle.contains("rule = W90"), "{rle}");
+
+        let update = app.update(Event::SetRule("W91".to_string()), &mut model);
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        send(&app, &mut model, [Event::StepBack, Event::Step]);
+        assert_eq!(app.view(&model).population, 11);
+    }
+
+    #[test]
+    /// block rules run back to where they started, and undo replays them
+    /// with the blocks of each generation
+    fn test_margolus() {
+        let (app, mut model) = start(Life::blinker());
+        let start = model.life.state.clone();
+        let critters = "MS,D15;1;2;12;4;10;9;7;8;6;5;11;3;13;14;0:T20,20";
+        send(&app, &mut model, [Event::SetRule(critters.to_string())]);
+        assert!(app.view(&model).reversible);
+        send(&app, &mut model, (0..3).map(|_| Event::Step));
+        send(&app, &mut model, [Event::StepBack]);
+        let undone = model.life.state.clone();
+        send(&app, &mut model, [Event::Step]);
+        let third = model.life.state.clone();
+
+        send(&app, &mut model, [Event::RunBackwards(true)]);
+        assert!(app.view(&model).backwards);
+        send(&app, &mut model, [Event::Step]);
+        assert_eq!(model.life.state, undone);
+        send(&app, &mut model, [Event::StepPow(1)]);
+        assert_eq!((model.stats.generation, &model.life.state), (0, &start));
+        // nothing comes before the first generation
+        send(&app, &mut model, [Event::Step]);
+        assert_eq!((model.stats.generation, &model.life.state), (0, &start));
+
+        send(
+            &app,
+            &mut model,
+            [Event::RunBackwards(false), Event::StepPow(1), Event::Step],
+        );
+        assert_eq!(model.life.state, third);
+        send(&app, &mut model, [Event::SetRule("B3/S23".to_string())]);
+        assert!(send(&app, &mut model, [Event::RunBackwards(true)]));
+    }
+
+    #[test]
+    /// stochastic runs repeat from the same seed, and undo replays them
+    fn test_chances() {
+        let (app, mut model) = start(Life::default());
+        let chances = Chances {
+            birth: 0.8,
+            death: 0.9,
+            noise: 0.01,
+        };
+        send(
+            &app,
+            &mut model,
+            [Event::SetChances(chances), Event::SetSeed(42)],
+        );
+        let start = model.life.clone();
+        send(&app, &mut model, [Event::StepPow(3)]);
+        let eighth = model.life.state.clone();
+        send(&app, &mut model, [Event::Step, Event::StepBack]);
+        assert_eq!(model.life.state, eighth);
+
+        model.life = start;
+        model.stats.reset();
+        send(&app, &mut model, [Event::SetSeed(42)]);
+        send(&app, &mut model, (0..8).map(|_| Event::Step));
+        assert_eq!(model.life.state, eighth);
+        assert_eq!(app.view(&model).chances, chances);
+
+        let update = app.update(
+            Event::SetChances(Chances {
+                noise: 1.5,
+                ..chances
+            }),
+            &mut model,
+        );
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        assert_eq!(model.chances, chances);
+    }
+
+    #[test]
+    /// chance and seed changes end the run, so stepping back replays each
+    /// part with what it was run with
+    fn test_chances_mid_run() {
+        let (app, mut model) = start(Life::default());
+        let chances = Chances {
+            birth: 0.8,
+            death: 0.9,
+            noise: 0.01,
+        };
+        send(
+            &app,
+            &mut model,
+            [Event::SetChances(chances), Event::SetSeed(7)],
+        );
+        let mut before = Vec::new();
+        for _ in 0..3 {
+            before.push(model.life.state.clone());
+            send(&app, &mut model, [Event::Step]);
+        }
+        let third = model.life.state.clone();
+        let noisier = Chances {
+            noise: 0.2,
+            ..chances
+        };
+        for change in [Event::SetChances(noisier), Event::SetSeed(8)] {
+            send(&app, &mut model, [change]);
+            let mut after = Vec::new();
+            for _ in 0..3 {
+                after.push(model.life.state.clone());
+                send(&app, &mut model, [Event::Step]);
+            }
+            for state in after.iter().rev() {
+                send(&app, &mut model, [Event::StepBack]);
+                assert_eq!(&model.life.state, state);
+            }
+            assert!(!app.view(&model).can_step_back);
+
+            send(&app, &mut model, [Event::Undo]);
+            assert_eq!((model.chances, model.seed), (chances, 7));
+            assert_eq!(model.life.state, third);
+            send(&app, &mut model, [Event::StepBack]);
+            assert_eq!(model.life.state, before[2]);
+            send(&app, &mut model, [Event::Step]);
+            assert_eq!(model.life.state, third);
+        }
+    }
+
+    #[test]
+    /// the census of a world is shown until the next one, rules it can not
+    /// run are refused
+    fn test_census() {
+        let tub = Life::new(&[[0, 4], [0, 6], [-1, 5], [1, 5]]);
+        let (app, mut model) = start(Life::blinker() | tub);
+        send(&app, &mut model, [Event::Step, Event::Census(3)]);
+        let census = app.view(&model).census.unwrap();
+        assert_eq!(
+            (census.generation, census.to_string().as_str()),
+            (1, "1×xp2_252x7")
+        );
+        send(&app, &mut model, [Event::Census(1)]);
+        let census = app.view(&model).census.unwrap();
+        assert_eq!(census.to_string(), "1×Blinker, 1×Tub");
+
+        send(&app, &mut model, [Event::SetRule("W30".to_string())]);
+        let update = app.update(Event::Census(1), &mut model);
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        assert_eq!(app.view(&model).census, Some(census));
+    }
+
+    #[test]
+    /// soups replace the cells of their rectangle as one undoable edit
+    fn test_random_fill() {
+        let (app, mut model) = start(Life::new(&[[0, 0], [20, 20]]));
+        let start = model.life.clone();
+        let fill = |density, symmetry| Event::RandomFill {
+            rect: [[7, 7], [-8, -8]],
+            density,
+            seed: 5,
+            symmetry,
+        };
+        send(&app, &mut model, [fill(0.0, Symmetry::C1)]);
+        assert_eq!(model.life.state_as_list(), [[20, 20]]);
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life, start);
+
+        send(&app, &mut model, [fill(0.4, Symmetry::D8)]);
+        let soup = model.life.clone();
+        assert!(soup.state.len() > 20);
+        let turned = (soup.state.keys())
+            .filter(|cell| *cell != &[20, 20])
+            .all(|[r, c]| soup.is_alive(&[*c, -1 - *r]));
+        assert!(turned && soup.is_alive(&[20, 20]));
+
+        let update = app.update(fill(1.5, Symmetry::C1), &mut model);
+        assert!(matches!(update.expect_one_effect(), Effect::Alert(_)));
+        assert_eq!(model.life, soup);
+
+        // rectangles across all the coordinates are too large, and ones at
+        // their edge are filled like any other
+        for rect in [
+            [[i32::MIN, 0], [i32::MAX, 0]],
+            [[i32::MIN, i32::MIN], [i32::MAX, i32::MAX]],
+        ] {
+            let fill = Event::RandomFill {
+                rect,
+                density: 0.5,
+                seed: 5,
+                symmetry: Symmetry::C1,
+            };
+            assert!(send(&app, &mut model, [fill]));
+            assert_eq!(model.life, soup);
+        }
+        let edge = Event::RandomFill {
+            rect: [[i32::MAX, i32::MAX], [i32::MAX - 7, i32::MAX - 7]],
+            density: 1.0,
+            seed: 5,
+            symmetry: Symmetry::D8,
+        };
+        assert!(!send(&app, &mut model, [edge]));
+        assert!(model.life.is_alive(&[i32::MAX, i32::MAX - 7]));
+    }
+
+    #[test]
+    /// the tiled engine runs the world like the naive one, and warns about
+    /// rules it leaves to it
+    fn test_tiles_engine() {
+        let (app, mut naive) = start(Life::default());
+        let mut tiled = Model::default();
+        assert!(!send(&app, &mut tiled, [Event::SetEngine(Engine::Tiles)]));
+        for model in [&mut naive, &mut tiled] {
+            send(&app, model, [Event::StepPow(4), Event::Step]);
+        }
+        assert_eq!(tiled.life.state, naive.life.state);
+        assert_eq!(app.view(&tiled).generation, 17);
+
+        let rule = Event::SetRule("B2n3/S23".to_string());
+        assert!(send(&app, &mut tiled, [rule]));
+    }
+
+    #[test]
+    /// hashlife and tiles keep their world between steps, edits and engine
+    /// switches reach them
+    fn test_engine_world() {
+        let (app, mut naive) = start(Life::glider());
+        let mut fast = Model {
+            life: Life::glider(),
+            engine: Engine::HashLife,
+            ..Default::default()
+        };
+        for engine in [Engine::HashLife, Engine::Tiles] {
+            send(&app, &mut fast, [Event::SetEngine(engine)]);
+            for model in [&mut naive, &mut fast] {
+                send(
+                    &app,
+                    model,
+                    [Event::StepPow(2), Event::ToggleCell([20, 20])],
+                );
+                model.advance_by(5);
+            }
+            assert_eq!(fast.life, naive.life);
+            assert_eq!(fast.engine_world, Some(fast.life.fingerprint()));
+        }
+        assert_eq!(app.view(&fast).generation, 18);
+    }
+
+    #[test]
+    /// engines stepping one generation at a time take shorter jumps than
+    /// hashlife
+    fn test_step_pow_limits() {
+        for engine in [Engine::Naive, Engine::Tiles] {
+            let (app, mut model) = start(Life::blinker());
+            model.engine = engine;
+            assert!(send(&app, &mut model, [Event::StepPow(20)]));
+            assert_eq!(model.stats.generation, 1 << MAX_LOOP_STEP_POW);
+        }
+
+        let (app, mut model) = start(Life::blinker());
+        model.engine = Engine::HashLife;
+        assert!(!send(&app, &mut model, [Event::StepPow(20)]));
+        assert!(send(&app, &mut model, [Event::StepPow(40)]));
+        assert_eq!(
+            model.stats.generation,
+            (1 << 20) + (1 << hashlife::MAX_STEP_POW)
+        );
+        assert_eq!(model.life, Life::blinker());
+    }
+
+    #[test]
+    /// a glider jumping past the largest coordinates stops the run instead of
+    /// wrapping around
+    fn test_world_out_of_bounds() {
+        let mut glider = Life::glider();
+        glider.translate(&[i32::MIN + 20, i32::MAX - 20]);
+        let (app, mut model) = start(glider.clone());
+        model.engine = Engine::HashLife;
+        assert!(!send(&app, &mut model, [Event::StepPow(6)]));
+        let before = model.life.clone();
+        model.runner.running = true;
+        assert!(send(&app, &mut model, [Event::StepPow(7)]));
+        assert!(!model.runner.running);
+        assert_eq!(model.life, before);
+        assert_eq!(model.stats.generation, 1 << 6);
+        // stepping back still replays to the world before
+        send(&app, &mut model, [Event::StepBack]);
+        assert_eq!(model.stats.generation, (1 << 6) - 1);
+    }
+
+    #[test]
+    /// turning a selection that is not square replaces the cells it turns onto
+    fn test_rotate_selection() {
+        let row: Vec<_> = (0..5).map(|col| [0, col]).collect();
+        let (app, mut model) = start(Life::new(&row));
+        let beside = [Event::ToggleCell([3, 0]), Event::ToggleCell([3, 5])];
+        let select = Event::SelectRect([0, 0], [1, 4]);
+        send(&app, &mut model, beside.into_iter().chain([select]));
+        let before = model.life.state.clone();
+        send(&app, &mut model, [Event::RotateSelection(Rotation::R90)]);
+        let column: Vec<_> = (0..5).map(|row| [row, 1]).chain([[3, 5]]).collect();
+        assert_eq!(model.life.state, Life::new(&column).state);
+        assert_eq!(app.view(&model).selection, Some([[0, 0], [4, 1]]));
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.life.state, before);
+    }
+
+    #[test]
+    fn test_larger_than_life() {
+        let (app, mut model) = start(Life::default());
+        let rle =
+            b"x = 12, y = 8, rule = R5,C0,M1,S34..58,B34..45,NM\n12o$12o$12o$12o$12o$12o$12o$12o!";
+        send(&app, &mut model, [Event::LoadWorld(rle.to_vec())]);
+        let view = app.view(&model);
+        assert_eq!(view.rule, "R5,C0,M1,S34..58,B34..45,NM");
+        assert_eq!(view.population, 96);
+
+        let mut expected = model.life.clone();
+        expected.tick(&model.rule);
+        assert!(send(&app, &mut model, [Event::SetEngine(Engine::HashLife)]));
+        send(&app, &mut model, [Event::Step]);
+        assert_eq!(model.life.state, expected.state);
+        assert_ne!(model.life.state.len(), 96);
+    }
+
+    #[test]
+    /// hexagonal rules switch the view to hexagons and clicks follow them
+    fn test_hexagonal() {
+        let (app, mut model) = start(Life::empty());
+        send(&app, &mut model, [Event::SetRule("B2/S34H".to_string())]);
+        let view = app.view(&model);
+        assert_eq!(view.layout, Layout::Hexagonal);
+
+        let cell = [3, -2];
+        let [x, y]: [f32; 2] = model.camera.cell2creen(&cell).into();
+        let centre = [x + view.cell_size / 2.0, y + view.cell_size / 2.0];
+        send(&app, &mut model, [Event::ToggleScreenCoord(centre)]);
+        assert_eq!(model.life.state_as_list(), vec![cell]);
+
+        send(
+            &app,
+            &mut model,
+            [Event::Undo, Event::SetRule("B3/S23".to_string())],
+        );
+        assert_eq!(app.view(&model).layout, Layout::Square);
+    }
+
+    #[test]
+    fn test_topology() {
+        let (app, mut model) = start(Life::new(&[[0, 0], [0, 9]]));
+        let torus = Topology::Torus {
+            width: 8,
+            height: 8,
+        };
+        send(&app, &mut model, [Event::SetTopology(torus)]);
+        let view = app.view(&model);
+        assert_eq!(view.rule, "B3/S23:T8,8");
+        assert_eq!(view.topology, torus);
+        assert!(view.world_box.is_some());
+        assert_eq!(model.life.state_as_list().len(), 2);
+        assert!(model.life.state.contains_key(&[0, 1]));
+
+        // toggles outside the world land on the cell they wrap to
+        send(&app, &mut model, [Event::ToggleCell([8, 8])]);
+        assert!(!model.life.state.contains_key(&[0, 0]));
+
+        send(&app, &mut model, [Event::ShowWrappedCopies(true)]);
+        let copies = app.view(&model).cell_coords.len();
+        assert!(copies > 1, "{copies}");
+
+        send(
+            &app,
+            &mut model,
+            [Event::SetRule("B3/S23:P1,1".to_string())],
+        );
+        assert_eq!(model.life.state.len(), 0);
+        send(&app, &mut model, [Event::Undo]);
+        assert_eq!(model.rule.topology(), torus);
+        assert!(model.life.state.contains_key(&[0, 1]));
+
+        assert!(send(&app, &mut model, [Event::SetEngine(Engine::HashLife)]));
+        send(&app, &mut model, [Event::StepPow(5)]);
+        assert_eq!(model.stats.generation, 32);
     }
 }
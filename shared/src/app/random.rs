@@ -0,0 +1,135 @@
+//! Stochastic rules, where births and deaths only happen with some chance and
+//! noise flips random cells each generation
+//!
+//! The randomness comes from a [Random] generator seeded by the user, and
+//! cells are always visited in the same order, so a run from the same seed is
+//! the same every time.
+use serde::{Deserialize, Serialize};
+
+use super::{CellCoord, CellMap, ALIVE};
+
+/// SplitMix64 pseudo random generator
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// uniform in `0.0..1.0`
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+    /// true with probability `p`
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.unit() < f64::from(p)
+    }
+    /// count of failures before the first success of trials succeeding with
+    /// probability `p`, so rare events can be placed without a draw per trial
+    fn skip(&mut self, p: f32) -> u64 {
+        let p = f64::from(p);
+        if p >= 1.0 {
+            return 0;
+        }
+        // 1 - unit is never 0, and the huge counts of tiny p saturate
+        ((1.0 - self.unit()).ln() / (1.0 - p).ln()) as u64
+    }
+}
+
+/// Probabilities of a stochastic rule, all certain and without noise for the
+/// plain rule
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Chances {
+    /// a cell the rule brings alive is born
+    pub birth: f32,
+    /// a live cell the rule kills, or starts decaying, dies
+    pub death: f32,
+    /// any cell of the world flips between dead and alive each generation
+    pub noise: f32,
+}
+
+impl Default for Chances {
+    fn default() -> Self {
+        Self {
+            birth: 1.0,
+            death: 1.0,
+            noise: 0.0,
+        }
+    }
+}
+
+impl Chances {
+    /// every probability is between 0 and 1
+    pub fn is_valid(&self) -> bool {
+        [self.birth, self.death, self.noise]
+            .iter()
+            .all(|p| (0.0..=1.0).contains(p))
+    }
+    /// the rule runs as without chances
+    pub fn is_certain(&self) -> bool {
+        self.birth >= 1.0 && self.death >= 1.0 && self.noise <= 0.0
+    }
+
+    /// undo the births and deaths from `before` to `after` that did not
+    /// happen, then flip the noise among the cells `area` spans
+    pub fn apply(
+        &self,
+        before: &CellMap,
+        after: &mut CellMap,
+        area: Option<[CellCoord; 2]>,
+        random: &mut Random,
+    ) {
+        let mut changed: Vec<(CellCoord, u8)> = after
+            .iter()
+            .filter(|(cell, _)| !before.contains_key(*cell))
+            .map(|(cell, state)| (*cell, *state))
+            .chain(
+                before
+                    .iter()
+                    .filter(|(cell, state)| **state == ALIVE && after.get(*cell) != Some(&ALIVE))
+                    .map(|(cell, state)| (*cell, *state)),
+            )
+            .collect();
+        changed.sort_unstable();
+        for (cell, state) in changed {
+            match before.get(&cell) {
+                None if !random.chance(self.birth) => {
+                    after.remove(&cell);
+                }
+                Some(_) if !random.chance(self.death) => {
+                    after.insert(cell, state);
+                }
+                _ => (),
+            }
+        }
+
+        let Some([[top, left], [bottom, right]]) = area else {
+            return;
+        };
+        if self.noise <= 0.0 {
+            return;
+        }
+        let width = u64::from(right.abs_diff(left)) + 1;
+        let cells = u64::from(bottom.abs_diff(top)) * width + width;
+        let mut index = random.skip(self.noise);
+        while index < cells {
+            let cell = [top + (index / width) as i32, left + (index % width) as i32];
+            match after.get(&cell) {
+                Some(_) => after.remove(&cell),
+                None => after.insert(cell, ALIVE),
+            };
+            index = index
+                .saturating_add(random.skip(self.noise))
+                .saturating_add(1);
+        }
+    }
+}
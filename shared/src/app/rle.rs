@@ -0,0 +1,449 @@
+//! Run Length Encoded patterns, the format used by Golly and most pattern collections
+//!
+//! ```text
+//! #N Glider
+//! #C The smallest spaceship
+//! x = 3, y = 3, rule = B3/S23
+//! bob$2bo$3o!
+//! ```
+//!
+//! Multi-state patterns write dead cells as `.` and states 1 to 24 as `A` to
+//! `X`, with a `p` to `y` prefix for the higher ones. Patterns in a loaded
+//! rule table are followed by its rule file, like LifeViewer reads them.
+use std::{collections::BTreeMap, fmt::Display};
+
+use super::{
+    format::{LoadError, Pattern, MAX_CELLS},
+    rule::{Rule, RuleError},
+    table::Tables,
+    CellCoord, ALIVE,
+};
+
+/// longest line written by [write]
+const LINE_LENGTH: usize = 70;
+
+/// `#N`, `#O` and `#C` lines
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Comments {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub notes: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RleError {
+    /// malformed `x = .., y = ..` line
+    Header(String),
+    Rule(RuleError),
+    /// unexpected character in the cell data
+    Token(char),
+    /// malformed position in a `#R`, `#P` or `#CXRLE` line
+    Position(String),
+}
+
+impl Display for RleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RleError::Header(line) => write!(f, "invalid rle header {line:?}"),
+            RleError::Rule(err) => write!(f, "invalid rle rule, {err}"),
+            RleError::Token(c) => write!(f, "unexpected {c:?} in rle data"),
+            RleError::Position(line) => write!(f, "invalid rle position {line:?}"),
+        }
+    }
+}
+
+impl std::error::Error for RleError {}
+
+impl From<RuleError> for RleError {
+    fn from(err: RuleError) -> Self {
+        RleError::Rule(err)
+    }
+}
+
+/// `x y` of a `#R`/`#P` line or `x,y` of `Pos=x,y`, as a [row, column] top left corner
+fn parse_position(text: &str) -> Result<CellCoord, RleError> {
+    let err = || RleError::Position(text.to_string());
+    let mut numbers = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<i32>().map_err(|_| err()));
+    match (numbers.next(), numbers.next()) {
+        (Some(x), Some(y)) => Ok([y?, x?]),
+        _ => Err(err()),
+    }
+}
+
+/// width, height and rule of a `x = 3, y = 3, rule = B3/S23` line
+fn parse_header(line: &str, tables: &Tables) -> Result<(i32, i32, Option<Rule>), RleError> {
+    let err = || RleError::Header(line.to_string());
+    let (mut width, mut height) = (None, None);
+    // the rule comes last and may hold commas, e.g. `rule = B3/S23:T40,30`
+    let (fields, rule) = match line.split_once("rule") {
+        Some((fields, rule)) => {
+            let rule = rule.trim_start().strip_prefix('=').ok_or_else(err)?;
+            let rule = Rule::parse(rule, tables)?;
+            (fields.trim_end().trim_end_matches(','), Some(rule))
+        }
+        None => (line, None),
+    };
+    for field in fields.split(',') {
+        let (key, value) = field.split_once('=').ok_or_else(err)?;
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = Some(value.parse().map_err(|_| err())?),
+            "y" => height = Some(value.parse().map_err(|_| err())?),
+            _ => (),
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(err()),
+    }
+}
+
+/// the pattern of an rle, with rules named after one of the `tables`
+pub fn parse(text: &str, tables: &Tables) -> Result<Pattern, LoadError> {
+    let mut pattern = Pattern::default();
+    let mut size = None;
+    let mut position = None;
+    let mut cursor: CellCoord = [0, 0];
+    let mut count: i32 = 0;
+    // `p`..`y` before a state letter, adding 24 for each step from `p`
+    let mut prefix: Option<u8> = None;
+    'lines: for line in text.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let (tag, content) = comment.split_at(comment.len().min(1));
+            let content = content.trim().to_string();
+            match tag {
+                "N" => pattern.comments.name = Some(content),
+                "O" => pattern.comments.author = Some(content),
+                "R" | "P" => position = Some(parse_position(&content)?),
+                "r" => pattern.rule = Some(Rule::parse(&content, tables).map_err(RleError::Rule)?),
+                "C" | "c" if content.starts_with("XRLE") => {
+                    let pos = content
+                        .split_whitespace()
+                        .find_map(|s| s.strip_prefix("Pos="));
+                    if let Some(pos) = pos {
+                        position = Some(parse_position(pos)?);
+                    }
+                }
+                _ => pattern.comments.notes.push(content),
+            }
+            continue;
+        }
+        if line.starts_with('x') && size.is_none() {
+            let (width, height, rule) = parse_header(line, tables)?;
+            size = Some([height, width]);
+            pattern.rule = rule.or(pattern.rule);
+            continue;
+        }
+        for c in line.chars() {
+            let run = count.max(1);
+            if prefix.is_some() && !c.is_ascii_uppercase() {
+                return Err(RleError::Token(c).into());
+            }
+            match c {
+                '0'..='9' => {
+                    count = count
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(c as i32 - '0' as i32))
+                        .ok_or(LoadError::CoordinateOverflow)?;
+                    continue;
+                }
+                'b' | '.' => {
+                    cursor[1] = cursor[1]
+                        .checked_add(run)
+                        .ok_or(LoadError::CoordinateOverflow)?
+                }
+                '$' => {
+                    let row = cursor[0]
+                        .checked_add(run)
+                        .ok_or(LoadError::CoordinateOverflow)?;
+                    cursor = [row, 0];
+                }
+                '!' => break 'lines,
+                'p'..='y' => {
+                    prefix = Some(c as u8 - b'p' + 1);
+                    continue;
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let state = match c {
+                        'A'..='X' => {
+                            let state = prefix.take().unwrap_or(0) as u32 * 24;
+                            u8::try_from(state + (c as u32 - 'A' as u32) + 1)
+                                .map_err(|_| RleError::Token(c))?
+                        }
+                        c if c.is_ascii_uppercase() => return Err(RleError::Token(c).into()),
+                        _ => ALIVE,
+                    };
+                    let total = pattern.cells.len() + run as usize;
+                    if total > MAX_CELLS {
+                        return Err(LoadError::TooManyCells(total));
+                    }
+                    let end = cursor[1]
+                        .checked_add(run)
+                        .ok_or(LoadError::CoordinateOverflow)?;
+                    pattern
+                        .cells
+                        .extend((cursor[1]..end).map(|col| [cursor[0], col]));
+                    pattern.states.extend((cursor[1]..end).map(|_| state));
+                    cursor[1] = end;
+                }
+                c if c.is_whitespace() => continue,
+                c => return Err(RleError::Token(c).into()),
+            }
+            count = 0;
+        }
+    }
+    if pattern.states.iter().all(|state| *state == ALIVE) {
+        pattern.states.clear();
+    }
+    // without an explicit position the pattern is centered
+    let [top, left] = position.unwrap_or_else(|| {
+        let [height, width] = size.unwrap_or([0, 0]);
+        [-height / 2, -width / 2]
+    });
+    for cell in pattern.cells.iter_mut() {
+        let row = cell[0].checked_add(top);
+        let col = cell[1].checked_add(left);
+        let (Some(row), Some(col)) = (row, col) else {
+            return Err(LoadError::CoordinateOverflow);
+        };
+        *cell = [row, col];
+    }
+    Ok(pattern)
+}
+
+/// collects rle tokens into lines of at most [LINE_LENGTH]
+struct Lines {
+    text: String,
+    line: String,
+}
+
+impl Lines {
+    fn push(&mut self, count: i32, tag: &str) {
+        let token = match count {
+            1 => tag.to_string(),
+            n => format!("{n}{tag}"),
+        };
+        if self.line.len() + token.len() > LINE_LENGTH {
+            self.text.push_str(&self.line);
+            self.text.push('\n');
+            self.line.clear();
+        }
+        self.line.push_str(&token);
+    }
+}
+
+/// letters of a multi-state cell, `A` for state 1 up to `yO` for 255
+fn state_tag(state: u8) -> String {
+    let (prefix, letter) = ((state - 1) / 24, (state - 1) % 24);
+    let letter = char::from(b'A' + letter);
+    match prefix {
+        0 => letter.to_string(),
+        prefix => format!("{}{letter}", char::from(b'p' + prefix - 1)),
+    }
+}
+
+pub fn write(cells: &[(CellCoord, u8)], rule: &Rule, comments: &Comments) -> String {
+    let mut rows: BTreeMap<i32, Vec<(i32, u8)>> = BTreeMap::new();
+    for ([row, col], state) in cells {
+        rows.entry(*row).or_default().push((*col, *state));
+    }
+    let top = rows.keys().next().copied().unwrap_or(0);
+    let bottom = rows.keys().last().copied().unwrap_or(-1);
+    let left = cells.iter().map(|(c, _)| c[1]).min().unwrap_or(0);
+    let right = cells.iter().map(|(c, _)| c[1]).max().unwrap_or(-1);
+    let multi_state = rule.states() > 2 || cells.iter().any(|(_, state)| *state != ALIVE);
+    let (dead, alive) = if multi_state { (".", "A") } else { ("b", "o") };
+
+    let mut text = String::new();
+    if let Some(name) = &comments.name {
+        text.push_str(&format!("#N {name}\n"));
+    }
+    if let Some(author) = &comments.author {
+        text.push_str(&format!("#O {author}\n"));
+    }
+    for note in &comments.notes {
+        text.push_str(&format!("#C {note}\n"));
+    }
+    text.push_str(&format!("#CXRLE Pos={left},{top}\n"));
+    text.push_str(&format!(
+        "x = {}, y = {}, rule = {rule}\n",
+        right - left + 1,
+        bottom - top + 1
+    ));
+
+    let mut lines = Lines {
+        text,
+        line: String::new(),
+    };
+    let mut last_row = top;
+    for (row, cols) in rows.iter_mut() {
+        if *row > last_row {
+            lines.push(row - last_row, "$");
+            last_row = *row;
+        }
+        cols.sort_unstable();
+        let mut col = left;
+        let mut runs = cols.iter().peekable();
+        while let Some(&(start, state)) = runs.next() {
+            let mut end = start + 1;
+            while runs.next_if_eq(&&(end, state)).is_some() {
+                end += 1;
+            }
+            if start > col {
+                lines.push(start - col, dead);
+            }
+            match state {
+                ALIVE => lines.push(end - start, alive),
+                state => lines.push(end - start, &state_tag(state)),
+            }
+            col = end;
+        }
+    }
+    lines.push(1, "!");
+    let text = lines.text + &lines.line + "\n";
+    match rule.table().filter(|table| !table.is_built_in()) {
+        Some(table) => text + table.source() + "\n",
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod test_rle {
+    use super::*;
+
+    /// parse knowing only the built in rule tables
+    fn parse(text: &str) -> Result<Pattern, LoadError> {
+        super::parse(text, &Tables::default())
+    }
+
+    const GLIDER: &str = "#N Glider
+#O Richard K. Guy
+#C The smallest, most common, and first discovered spaceship.
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!";
+
+    fn sorted(mut cells: Vec<CellCoord>) -> Vec<CellCoord> {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn test_parse_glider() {
+        let pattern = parse(GLIDER).unwrap();
+        assert_eq!(pattern.comments.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.comments.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(pattern.comments.notes.len(), 1);
+        assert_eq!(pattern.rule, Some(Rule::default()));
+        insta::assert_ron_snapshot!(sorted(pattern.cells), @r#"
+        [
+          (-1, 0),
+          (0, 1),
+          (1, -1),
+          (1, 0),
+          (1, 1),
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_write_read() {
+        let pattern = parse(GLIDER).unwrap();
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        let text = write(&pattern.cell_states(), &highlife, &pattern.comments);
+        insta::assert_snapshot!(text, @r#"
+        #N Glider
+        #O Richard K. Guy
+        #C The smallest, most common, and first discovered spaceship.
+        #CXRLE Pos=-1,-1
+        x = 3, y = 3, rule = B36/S23
+        bo$2bo$3o!
+        "#);
+        let read = parse(&text).unwrap();
+        assert_eq!(read.rule, Some(highlife));
+        assert_eq!(read.comments, pattern.comments);
+        assert_eq!(sorted(read.cells), sorted(pattern.cells));
+    }
+
+    #[test]
+    /// topologies and Larger than Life rules hold commas of their own
+    fn test_rule_with_commas() {
+        for rulestring in ["B3/S23:T40,30", "R5,C0,M1,S34..58,B34..45,NM:P100,80"] {
+            let rule: Rule = rulestring.parse().unwrap();
+            let text = write(&[([0, 0], 1)], &rule, &Comments::default());
+            assert!(text.contains(&format!("rule = {rulestring}\n")), "{text}");
+            assert_eq!(parse(&text).unwrap().rule, Some(rule));
+        }
+        assert!(parse("x = 1, y = 1, rule B3/S23\no!").is_err());
+    }
+
+    #[test]
+    /// runs, blank rows and long lines
+    fn test_round_trip() {
+        let mut cells: Vec<CellCoord> = (0..100).map(|col| [3, col * 2]).collect();
+        cells.extend([[0, 5], [0, 6], [0, 7], [0, 9], [7, -4]]);
+        let states: Vec<_> = cells.iter().map(|cell| (*cell, ALIVE)).collect();
+        let text = write(&states, &Rule::default(), &Comments::default());
+        assert!(text.lines().all(|line| line.len() <= LINE_LENGTH));
+        let read = parse(&text).unwrap();
+        assert_eq!(sorted(read.cells), sorted(cells));
+    }
+
+    #[test]
+    fn test_multi_state() {
+        let pattern = parse("x = 5, y = 2, rule = B2/S/C3\n.2AB$pAyOo!").unwrap();
+        assert_eq!(pattern.rule, Some("B2/S/C3".parse().unwrap()));
+        let mut cells = pattern.cell_states();
+        cells.sort();
+        insta::assert_ron_snapshot!(cells, @r#"
+        [
+          ((-1, -1), 1),
+          ((-1, 0), 1),
+          ((-1, 1), 2),
+          ((0, -2), 25),
+          ((0, -1), 255),
+          ((0, 0), 1),
+        ]
+        "#);
+        let text = write(&cells, &"B2/S/C255".parse().unwrap(), &Comments::default());
+        assert!(text.ends_with(".2AB$pAyOA!\n"), "{text}");
+        let mut read = parse(&text).unwrap().cell_states();
+        read.sort();
+        assert_eq!(read, cells);
+        assert_eq!(
+            parse("x = 2, y = 1\npb!").unwrap_err(),
+            RleError::Token('b').into()
+        );
+        assert_eq!(
+            parse("x = 2, y = 1\nyY!").unwrap_err(),
+            RleError::Token('Y').into()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            parse("x = 3, y = 3\nbo?!").unwrap_err(),
+            RleError::Token('?').into()
+        );
+        assert_eq!(
+            parse("x = 3, y = 3, rule = B9/S23\nbo!").unwrap_err(),
+            RleError::Rule(RuleError::Count('9')).into()
+        );
+        assert!(matches!(parse("x = a\nbo!"), Err(LoadError::Syntax(_))));
+        assert_eq!(
+            parse("x = 3, y = 3\n99999999999o!").unwrap_err(),
+            LoadError::CoordinateOverflow
+        );
+        assert_eq!(
+            parse("#R 2147483647 0\n2o!").unwrap_err(),
+            LoadError::CoordinateOverflow
+        );
+        assert_eq!(
+            parse("x = 3, y = 3\n99999999o!").unwrap_err(),
+            LoadError::TooManyCells(99999999)
+        );
+    }
+}
@@ -0,0 +1,567 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use super::{
+    adjacency::Adjacency,
+    hensel::{Neighbourhood, Transitions, LETTERS},
+    ltl::RangeRule,
+    margolus::Margolus,
+    table::{RuleTable, Tables},
+    topology::{Topology, TopologyError},
+    wolfram::Wolfram,
+};
+
+/// Outer totalistic rule, e.g. Conway's life is `B3/S23`, or an isotropic
+/// non-totalistic one in Hensel notation such as `B2n3/S23-q`
+///
+/// `birth`/`survive` hold the neighbourhoods in which a cell is born/survives.
+/// An `H` or `V` suffix picks the hexagonal or von Neumann [Adjacency], which
+/// only count neighbours, and a `:T40,30` like suffix sets the [Topology].
+///
+/// "Generations" rules such as Brian's Brain, `B2/S/C3`, have more than two
+/// `states`: a live cell that does not survive decays through the states
+/// `2..states` before it is dead, and only cells in state 1 count as neighbours.
+///
+/// Larger than Life rules like `R5,C0,M1,S34..58,B34..45,NM` count neighbours
+/// further away, they are kept as a [RangeRule] and ignore `birth`/`survive`,
+/// as do rules named after a [RuleTable] such as `Wireworld` and the one
+/// dimensional [Wolfram] rules like `W30` and the [Margolus] block rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    birth: Transitions,
+    survive: Transitions,
+    /// count of cell states including dead, 2 for plain life
+    states: u8,
+    adjacency: Adjacency,
+    range: Option<RangeRule>,
+    table: Option<Arc<RuleTable>>,
+    wolfram: Option<Wolfram>,
+    margolus: Option<Margolus>,
+    topology: Topology,
+}
+
+/// state of a live cell, higher states are decaying
+pub const ALIVE: u8 = 1;
+
+impl Default for Rule {
+    /// Conway's game of life, B3/S23
+    fn default() -> Self {
+        Self {
+            birth: Transitions::totalistic(1 << 3),
+            survive: Transitions::totalistic(1 << 2 | 1 << 3),
+            states: 2,
+            adjacency: Adjacency::Moore,
+            range: None,
+            table: None,
+            wolfram: None,
+            margolus: None,
+            topology: Topology::Plane,
+        }
+    }
+}
+
+impl Rule {
+    /// dead cell with the `neighbours` live comes alive
+    pub const fn birth(&self, neighbours: Neighbourhood) -> bool {
+        self.applies(&self.birth, neighbours)
+    }
+    /// live cell with the `neighbours` live stays alive
+    pub const fn survive(&self, neighbours: Neighbourhood) -> bool {
+        self.applies(&self.survive, neighbours)
+    }
+    const fn applies(&self, transitions: &Transitions, neighbours: Neighbourhood) -> bool {
+        match self.adjacency {
+            Adjacency::Moore => transitions.contains(neighbours),
+            _ => transitions.letters(neighbours.count_ones() as u8) != 0,
+        }
+    }
+    pub const fn adjacency(&self) -> Adjacency {
+        self.adjacency
+    }
+    /// the Larger than Life rule, if this is one
+    pub fn range(&self) -> Option<&RangeRule> {
+        self.range.as_ref()
+    }
+    /// the one dimensional rule, if this is one
+    pub const fn wolfram(&self) -> Option<Wolfram> {
+        self.wolfram
+    }
+    /// the block rule, if this is one
+    pub const fn margolus(&self) -> Option<Margolus> {
+        self.margolus
+    }
+    /// `birth`/`survive` decide the next generation, rather than a range,
+    /// table, one dimensional or block rule
+    pub const fn uses_transitions(&self) -> bool {
+        self.range.is_none()
+            && self.table.is_none()
+            && self.wolfram.is_none()
+            && self.margolus.is_none()
+    }
+    /// the transition table, if the rule has one
+    pub fn table(&self) -> Option<&RuleTable> {
+        self.table.as_deref()
+    }
+    /// the rule of `table`, on an infinite plane
+    pub fn from_table(table: Arc<RuleTable>) -> Self {
+        Self {
+            states: table.states(),
+            adjacency: table.adjacency(),
+            table: Some(table),
+            ..Self::default()
+        }
+    }
+    pub const fn states(&self) -> u8 {
+        self.states
+    }
+    /// state after `state`, 0 once a decaying cell is dead or for states the
+    /// rule does not have
+    pub const fn decay(&self, state: u8) -> u8 {
+        match state.checked_add(1) {
+            Some(next) if next < self.states => next,
+            _ => 0,
+        }
+    }
+    pub const fn topology(&self) -> Topology {
+        self.topology
+    }
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Self { topology, ..self }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    /// expected two sections separated by a '/'
+    Sections(String),
+    /// neighbour count outside 0..=8
+    Count(char),
+    /// Hensel letter that does not exist for the neighbour count before it
+    Letter(u8, char),
+    /// state count outside 2..=255
+    States(String),
+    /// birth with no live neighbours, which would fill all of space
+    BirthOnZero(String),
+    /// B/S rule with a range or neighbourhood suffix that only Larger than
+    /// Life rules have
+    RangeSuffix(String),
+    /// Larger than Life rule with a missing or invalid field
+    Range(String),
+    /// one dimensional rule out of range, or filling an infinite plane
+    Wolfram(String),
+    /// block rule that is not a permutation or does not fit the topology
+    Margolus(String),
+    Topology(TopologyError),
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::Sections(rule) => {
+                write!(
+                    f,
+                    "expected a rule like B3/S23 or the name of a loaded @RULE file, got {rule:?}"
+                )
+            }
+            RuleError::Count(c) => write!(f, "{c:?} is not a neighbour count (0-8)"),
+            RuleError::Letter(count, c) => {
+                write!(f, "{c:?} is not a Hensel letter for {count} neighbours")
+            }
+            RuleError::States(states) => {
+                write!(
+                    f,
+                    "expected a state count like C3 between 2 and 255, got {states:?}"
+                )
+            }
+            RuleError::BirthOnZero(rule) => write!(
+                f,
+                "rules with B0 bring all of empty space alive and are not supported, got {rule:?}"
+            ),
+            RuleError::RangeSuffix(rule) => write!(
+                f,
+                "neighbourhoods wider than the adjacent cells are written as Larger than \
+                 Life rules like R2,C0,M0,S2..3,B3..3,NM, got {rule:?}"
+            ),
+            RuleError::Range(rule) => write!(
+                f,
+                "expected a Larger than Life rule like R5,C0,M1,S34..58,B34..45,NM, got {rule:?}"
+            ),
+            RuleError::Wolfram(rule) => write!(
+                f,
+                "expected a 1D rule like W30 or W10T, where odd codes need a finite \
+                 width like W1:T100,100, got {rule:?}"
+            ),
+            RuleError::Margolus(rule) => write!(
+                f,
+                "expected a block rule like MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15 \
+                 permuting 0-15, on a plane or an even sized bounded world or torus, where \
+                 rules filling empty blocks need a finite world, got {rule:?}"
+            ),
+            RuleError::Topology(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// neighbourhoods listed in a section like `23` or `2n3-q`, a count without
+/// letters takes all of them and letters after a `-` are left out
+fn parse_transitions(section: &str) -> Result<Transitions, RuleError> {
+    let mut transitions = Transitions::default();
+    // count the letters belong to and whether they are left out
+    let mut current = None;
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='8' => {
+                let count = c as u8 - b'0';
+                let negated = chars.next_if_eq(&'-').is_some();
+                let listed = matches!(chars.peek(), Some(c) if c.is_ascii_lowercase());
+                if negated || !listed {
+                    transitions.set_letters(count, Transitions::all(count));
+                }
+                current = Some((count, negated));
+            }
+            c if c.is_ascii_lowercase() => {
+                let Some((count, negated)) = current else {
+                    return Err(RuleError::Count(c));
+                };
+                let Some(letter) = LETTERS[count as usize].find(c) else {
+                    return Err(RuleError::Letter(count, c));
+                };
+                let letters = transitions.letters(count);
+                transitions.set_letters(
+                    count,
+                    match negated {
+                        true => letters & !(1 << letter),
+                        false => letters | 1 << letter,
+                    },
+                );
+            }
+            c => return Err(RuleError::Count(c)),
+        }
+    }
+    Ok(transitions)
+}
+
+/// counts beyond the neighbours of `adjacency`, or letters when it is not Moore
+fn check_adjacency(section: &str, adjacency: Adjacency) -> Result<(), RuleError> {
+    if adjacency == Adjacency::Moore {
+        return Ok(());
+    }
+    let mut count = 0;
+    for c in section.chars() {
+        match c.to_digit(10) {
+            Some(n) if n as usize > adjacency.offsets().len() => return Err(RuleError::Count(c)),
+            Some(n) => count = n as u8,
+            None if c.is_ascii_lowercase() => return Err(RuleError::Letter(count, c)),
+            None => (),
+        }
+    }
+    Ok(())
+}
+
+/// counts and letters of `transitions`, listing whichever of the letters in
+/// or left out of a count is shorter
+fn write_transitions(transitions: &Transitions) -> String {
+    let mut text = String::new();
+    for count in 0..=8 {
+        let (letters, all) = (transitions.letters(count), Transitions::all(count));
+        if letters == 0 {
+            continue;
+        }
+        text.push(char::from(b'0' + count));
+        let names = |mask: u16| -> String {
+            (LETTERS[count as usize].chars().enumerate())
+                .filter(|(i, _)| mask & 1 << i != 0)
+                .map(|(_, c)| c)
+                .collect()
+        };
+        let (listed, missing) = (names(letters), names(all & !letters));
+        if missing.is_empty() {
+            continue;
+        } else if missing.len() < listed.len() {
+            text.push('-');
+            text.push_str(&missing);
+        } else {
+            text.push_str(&listed);
+        }
+    }
+    text
+}
+
+/// `C3` or `3`
+fn parse_states(section: &str) -> Result<u8, RuleError> {
+    let count = strip_letter(section, 'C').unwrap_or(section);
+    match count.parse() {
+        Ok(states) if states >= 2 => Ok(states),
+        _ => Err(RuleError::States(section.to_string())),
+    }
+}
+
+fn strip_letter(section: &str, letter: char) -> Option<&str> {
+    section
+        .strip_prefix(letter)
+        .or_else(|| section.strip_prefix(letter.to_ascii_lowercase()))
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    /// like [Rule::parse] with only the built in tables
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, &Tables::default())
+    }
+}
+
+impl Rule {
+    /// Accepts `B36/S23`, `S23/B36` and the older survival first `23/36`,
+    /// optionally followed by a state count such as `/C3` or `/3`, a
+    /// neighbourhood suffix `H` or `V` and a topology such as `:T40,30`, a
+    /// Larger than Life rule like `R5,C0,M1,S34..58,B34..45,NM`, or the name
+    /// of one of the `tables`
+    pub fn parse(s: &str, tables: &Tables) -> Result<Self, RuleError> {
+        let (rulestring, topology) = match s.trim().split_once(':') {
+            Some((rulestring, topology)) => {
+                (rulestring, topology.parse().map_err(RuleError::Topology)?)
+            }
+            None => (s.trim(), Topology::Plane),
+        };
+        if let Some(range) = strip_letter(rulestring, 'R') {
+            if range.starts_with(|c: char| c.is_ascii_digit()) {
+                let Some((range, states)) = RangeRule::parse(rulestring) else {
+                    return Err(RuleError::Range(rulestring.to_string()));
+                };
+                return Ok(Self {
+                    states,
+                    range: Some(range),
+                    topology,
+                    ..Self::default()
+                });
+            }
+        }
+        if strip_letter(rulestring, 'M').map_or(false, |rest| rest.starts_with(['S', 's'])) {
+            let margolus = Margolus::parse(rulestring)
+                .filter(|margolus| margolus.runs_in(topology))
+                .ok_or_else(|| RuleError::Margolus(s.trim().to_string()))?;
+            return Ok(Self {
+                margolus: Some(margolus),
+                topology,
+                ..Self::default()
+            });
+        }
+        if let Some(code) = strip_letter(rulestring, 'W') {
+            if code.starts_with(|c: char| c.is_ascii_digit()) {
+                let wolfram = Wolfram::parse(rulestring)
+                    .filter(|wolfram| !wolfram.fills_space() || topology != Topology::Plane);
+                let Some(wolfram) = wolfram else {
+                    return Err(RuleError::Wolfram(s.trim().to_string()));
+                };
+                return Ok(Self {
+                    wolfram: Some(wolfram),
+                    topology,
+                    ..Self::default()
+                });
+            }
+        }
+        if !rulestring.contains('/') {
+            if let Some(table) = tables.find(rulestring) {
+                return Ok(Self::from_table(table).with_topology(topology));
+            }
+        }
+        let (rulestring, adjacency) = Adjacency::strip_suffix(rulestring);
+        if rulestring.contains('/') && Adjacency::is_range_suffix(rulestring) {
+            return Err(RuleError::RangeSuffix(s.trim().to_string()));
+        }
+        let Some((first, second)) = rulestring.split_once('/') else {
+            return Err(RuleError::Sections(rulestring.to_string()));
+        };
+        let (second, states) = match second.split_once('/') {
+            Some((second, states)) => (second, parse_states(states)?),
+            None => (second, 2),
+        };
+        let (birth, survive) = match (strip_letter(first, 'B'), strip_letter(second, 'S')) {
+            (Some(birth), Some(survive)) => (birth, survive),
+            _ => match (strip_letter(first, 'S'), strip_letter(second, 'B')) {
+                (Some(survive), Some(birth)) => (birth, survive),
+                (None, None) => (second, first),
+                _ => return Err(RuleError::Sections(rulestring.to_string())),
+            },
+        };
+        check_adjacency(birth, adjacency)?;
+        check_adjacency(survive, adjacency)?;
+        let rule = Self {
+            birth: parse_transitions(birth)?,
+            survive: parse_transitions(survive)?,
+            states,
+            adjacency,
+            range: None,
+            table: None,
+            wolfram: None,
+            margolus: None,
+            topology,
+        };
+        // no engine steps the dead cells away from the live ones
+        if rule.birth(0) {
+            return Err(RuleError::BirthOnZero(s.trim().to_string()));
+        }
+        Ok(rule)
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(table) = &self.table {
+            write!(f, "{}", table.name())?;
+        } else if let Some(wolfram) = &self.wolfram {
+            wolfram.write(f)?;
+        } else if let Some(margolus) = &self.margolus {
+            margolus.write(f)?;
+        } else if let Some(range) = &self.range {
+            range.write(f, self.states)?;
+        } else {
+            write!(
+                f,
+                "B{}/S{}",
+                write_transitions(&self.birth),
+                write_transitions(&self.survive)
+            )?;
+            if self.states > 2 {
+                write!(f, "/C{}", self.states)?;
+            }
+            write!(f, "{}", self.adjacency.suffix())?;
+        }
+        match self.topology {
+            Topology::Plane => Ok(()),
+            topology => write!(f, ":{topology}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_rule {
+    use super::*;
+
+    #[test]
+    fn test_notations() {
+        let conway = Rule::default();
+        for notation in ["B3/S23", "b3/s23", "S23/B3", "23/3"] {
+            assert_eq!(notation.parse().as_ref(), Ok(&conway), "{notation}");
+        }
+        assert_eq!(conway.to_string(), "B3/S23");
+
+        let seeds: Rule = "B2/S".parse().unwrap();
+        assert_eq!(seeds.to_string(), "B2/S");
+        assert!((0..=255).all(|n| !seeds.survive(n)));
+
+        let highlife: Rule = " B36/S23 ".parse().unwrap();
+        assert!(highlife.birth(0b0011_1111));
+        assert!(!highlife.birth(0b1000_0001));
+        assert_eq!(highlife.to_string(), "B36/S23");
+
+        let torus: Rule = "B3/S23:t40,30".parse().unwrap();
+        assert_eq!(torus.to_string(), "B3/S23:T40,30");
+        assert_eq!(torus.with_topology(Topology::Plane), conway);
+    }
+
+    #[test]
+    fn test_generations() {
+        let brians_brain: Rule = "B2/S/C3".parse().unwrap();
+        assert_eq!("/2/3".parse().as_ref(), Ok(&brians_brain));
+        assert_eq!(brians_brain.to_string(), "B2/S/C3");
+        assert_eq!(brians_brain.states(), 3);
+        assert_eq!(brians_brain.decay(ALIVE), 2);
+        assert_eq!(brians_brain.decay(2), 0);
+        // states the rule does not have are dead, without overflowing
+        assert_eq!(brians_brain.decay(7), 0);
+        assert_eq!(brians_brain.decay(u8::MAX), 0);
+
+        let star_wars: Rule = "345/2/4".parse().unwrap();
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+        assert_eq!(
+            "B2/S345/C4:T10,10".parse::<Rule>().unwrap().to_string(),
+            "B2/S345/C4:T10,10"
+        );
+        assert_eq!("B3/S23/C2".parse(), Ok(Rule::default()));
+        assert_eq!(Rule::default().decay(ALIVE), 0);
+        assert_eq!(
+            "B2/S/C1".parse::<Rule>(),
+            Err(RuleError::States("C1".to_string()))
+        );
+        assert!("B2/S/C256".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_hensel() {
+        // a count with every letter is the same as the plain count
+        assert_eq!("B3/S2ceaikn3".parse(), Ok(Rule::default()));
+        assert_eq!("B3-/S23".parse(), Ok(Rule::default()));
+
+        let rule: Rule = "b2n3/s23-q".parse().unwrap();
+        assert_eq!(rule.to_string(), "B2n3/S23-q");
+        // opposite corners NW and SE, then corners NW and NE
+        assert!(rule.birth(0b1000_0001));
+        assert!(!rule.birth(0b0000_0101));
+        // N, NE and SW are a 3q, N, NE and E a 3a
+        assert!(!rule.survive(0b0010_0110));
+        assert!(rule.survive(0b0001_0110));
+
+        // letters are written in canonical order, or as those left out when shorter
+        for (rule, canonical) in [
+            ("B3/S2nc4ec", "B3/S2cn4ce"),
+            ("B3ceaikn/S23", "B3-jqry/S23"),
+            ("B3-jqry/S23", "B3-jqry/S23"),
+            ("B2-ce/S", "B2-ce/S"),
+            ("B2kc/S", "B2ck/S"),
+            ("B345/S5y6-a7e:T10,10", "B345/S5y6-a7e:T10,10"),
+        ] {
+            assert_eq!(rule.parse::<Rule>().unwrap().to_string(), canonical);
+        }
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let hex: Rule = "B2/S34H".parse().unwrap();
+        assert_eq!(hex.adjacency(), Adjacency::Hexagonal);
+        assert_eq!(hex.to_string(), "B2/S34H");
+        // any two of the six neighbours
+        assert!(hex.birth(0b00_1001));
+        assert!(!hex.birth(0b00_0111));
+
+        let von_neumann: Rule = "b1/s1v".parse().unwrap();
+        assert_eq!(von_neumann.adjacency(), Adjacency::VonNeumann);
+        assert_eq!(von_neumann.to_string(), "B1/S1V");
+        assert_eq!(
+            "B2/S/C3H:T10,10".parse::<Rule>().unwrap().to_string(),
+            "B2/S/C3H:T10,10"
+        );
+        assert_eq!("B5/SV".parse::<Rule>(), Err(RuleError::Count('5')));
+        assert_eq!("B7/S34H".parse::<Rule>(), Err(RuleError::Count('7')));
+        assert_eq!("B2n/SH".parse::<Rule>(), Err(RuleError::Letter(2, 'n')));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            "B3S23".parse::<Rule>(),
+            Err(RuleError::Sections("B3S23".to_string()))
+        );
+        assert_eq!("B39/S23".parse::<Rule>(), Err(RuleError::Count('9')));
+        assert_eq!("B3/Sx".parse::<Rule>(), Err(RuleError::Count('x')));
+        assert!("B3/B23".parse::<Rule>().is_err());
+        assert_eq!("B2z/S".parse::<Rule>(), Err(RuleError::Letter(2, 'z')));
+        assert_eq!("B0c/S".parse::<Rule>(), Err(RuleError::Letter(0, 'c')));
+        assert_eq!("B3/Sc".parse::<Rule>(), Err(RuleError::Count('c')));
+        for rule in ["B0/S8", "B02/S", "S8/B0", "B0/S1/C3H", "B0/S:T10,10"] {
+            let err = rule.parse::<Rule>();
+            assert_eq!(err, Err(RuleError::BirthOnZero(rule.to_string())));
+        }
+        for rule in ["B3/S23R2", "B3/S23NM", "B3/S23NN:T10,10", "B2/S34R2H"] {
+            let err = rule.parse::<Rule>();
+            assert_eq!(err, Err(RuleError::RangeSuffix(rule.to_string())));
+        }
+        assert!(matches!(
+            "B3/S23:T0,5".parse::<Rule>(),
+            Err(RuleError::Topology(_))
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+//! Rectangular selection of cells and the clipboard it is copied to
+//!
+//! Cells are moved in `i64`, since a rectangle may span more than half the
+//! coordinates, and only into rectangles checked to fit in them.
+use super::{library::Rotation, CellCoord, Life};
+
+/// Inclusive block of cells from the top left `min` to the bottom right `max`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub min: CellCoord,
+    pub max: CellCoord,
+}
+
+impl Rect {
+    /// rectangle spanned by two opposite corners in any order
+    pub fn from_corners(a: CellCoord, b: CellCoord) -> Self {
+        Self {
+            min: [a[0].min(b[0]), a[1].min(b[1])],
+            max: [a[0].max(b[0]), a[1].max(b[1])],
+        }
+    }
+    pub const fn contains(&self, cell: &CellCoord) -> bool {
+        self.min[0] <= cell[0]
+            && cell[0] <= self.max[0]
+            && self.min[1] <= cell[1]
+            && cell[1] <= self.max[1]
+    }
+    /// rectangle of `extent` from the top left `min`, none when it goes past
+    /// the largest coordinates
+    pub fn at(min: CellCoord, [rows, cols]: [u32; 2]) -> Option<Self> {
+        let max = [
+            min[0].checked_add_unsigned(rows)?,
+            min[1].checked_add_unsigned(cols)?,
+        ];
+        Some(Self { min, max })
+    }
+    /// [rows, columns] less one
+    pub const fn extent(&self) -> [u32; 2] {
+        [
+            self.max[0].abs_diff(self.min[0]),
+            self.max[1].abs_diff(self.min[1]),
+        ]
+    }
+    pub const fn corners(&self) -> [CellCoord; 2] {
+        [self.min, self.max]
+    }
+}
+
+/// Cells inside `rect`
+pub fn copy(life: &Life, rect: &Rect) -> Life {
+    let mut copy = Life::empty();
+    copy.state.extend(
+        life.state
+            .iter()
+            .filter(|(cell, _)| rect.contains(cell))
+            .map(|(cell, state)| (*cell, *state)),
+    );
+    copy
+}
+
+/// moves each cell to where `f` takes it
+fn move_wide(cells: &mut Life, f: impl Fn([i64; 2]) -> [i64; 2]) {
+    cells.move_cells(|[row, col]| {
+        let [row, col] = f([row.into(), col.into()]);
+        Some([i32::try_from(row).ok()?, i32::try_from(col).ok()?])
+    });
+}
+
+/// Moves `cells`, copied from `rect`, into the rectangle of its size with
+/// the top left corner `at`, which is returned. None when that goes past the
+/// largest coordinates.
+pub fn paste(cells: &mut Life, rect: &Rect, at: CellCoord) -> Option<Rect> {
+    let moved = Rect::at(at, rect.extent())?;
+    let [rows, cols] = [0, 1].map(|i| i64::from(at[i]) - i64::from(rect.min[i]));
+    move_wide(cells, |[row, col]| [row + rows, col + cols]);
+    Some(moved)
+}
+
+/// Turns `cells`, copied from `rect`, into a rectangle sharing the top left
+/// corner of `rect`, which is returned. None when that goes past the largest
+/// coordinates.
+pub fn rotate(cells: &mut Life, rect: &Rect, rotation: Rotation) -> Option<Rect> {
+    let [rows, cols] = rect.extent();
+    let extent = match rotation {
+        Rotation::R0 | Rotation::R180 => [rows, cols],
+        Rotation::R90 | Rotation::R270 => [cols, rows],
+    };
+    let turned = Rect::at(rect.min, extent)?;
+    let [top, left] = rect.min.map(i64::from);
+    let [bottom, right] = rect.max.map(i64::from);
+    move_wide(cells, |[row, col]| match rotation {
+        Rotation::R0 => [row, col],
+        Rotation::R90 => [top + col - left, left + bottom - row],
+        Rotation::R180 => [top + bottom - row, left + right - col],
+        Rotation::R270 => [top + right - col, left + row - top],
+    });
+    Some(turned)
+}
+
+/// Mirrors `cells`, copied from `rect`, top to bottom or left to right
+/// inside `rect`
+pub fn flip(cells: &mut Life, rect: &Rect, rows: bool) {
+    let [top, left] = rect.min.map(i64::from);
+    let [bottom, right] = rect.max.map(i64::from);
+    move_wide(cells, |[row, col]| match rows {
+        true => [top + bottom - row, col],
+        false => [row, left + right - col],
+    });
+}
+
+#[cfg(test)]
+mod test_selection {
+    use super::*;
+
+    fn sorted(life: &Life) -> Vec<CellCoord> {
+        let mut cells = life.state_as_list();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn test_copy() {
+        let rect = Rect::from_corners([1, 3], [-1, 0]);
+        assert_eq!(rect.corners(), [[-1, 0], [1, 3]]);
+        let life = Life::new(&[[-1, 0], [1, 3], [2, 3], [0, -1]]);
+        assert_eq!(sorted(&copy(&life, &rect)), vec![[-1, 0], [1, 3]]);
+    }
+
+    #[test]
+    /// rectangles spanning all the coordinates move without overflowing, and
+    /// moves past their edge are refused
+    fn test_extreme_corners() {
+        let tall = Rect::from_corners([i32::MIN, 0], [i32::MAX, 1]);
+        assert_eq!(tall.extent(), [u32::MAX, 1]);
+        let mut cells = Life::new(&[[i32::MIN, 0], [i32::MAX, 1]]);
+        flip(&mut cells, &tall, true);
+        assert_eq!(sorted(&cells), vec![[i32::MIN, 1], [i32::MAX, 0]]);
+        assert_eq!(rotate(&mut cells, &tall, Rotation::R90), None);
+        let turned = rotate(&mut cells, &tall, Rotation::R180).unwrap();
+        assert_eq!(turned, tall);
+        assert_eq!(sorted(&cells), vec![[i32::MIN, 1], [i32::MAX, 0]]);
+
+        let moved = paste(&mut cells, &tall, [i32::MIN, -5]).unwrap();
+        assert_eq!(moved.corners(), [[i32::MIN, -5], [i32::MAX, -4]]);
+        assert_eq!(sorted(&cells), vec![[i32::MIN, -4], [i32::MAX, -5]]);
+        assert_eq!(paste(&mut cells, &tall, [0, 0]), None);
+        let corner = Rect::from_corners([0, 0], [1, 1]);
+        assert_eq!(paste(&mut Life::empty(), &corner, [0, i32::MAX]), None);
+    }
+
+    #[test]
+    /// an L in a 3x2 box, turned and flipped in place
+    fn test_transforms() {
+        let rect = Rect::from_corners([10, 20], [12, 21]);
+        let l_shape = Life::new(&[[10, 20], [11, 20], [12, 20], [12, 21]]);
+        let transformed = |f: &dyn Fn(&mut Life) -> Rect| {
+            let mut cells = l_shape.clone();
+            let rect = f(&mut cells);
+            assert!(cells.state.keys().all(|cell| rect.contains(cell)));
+            (rect.corners(), sorted(&cells))
+        };
+        insta::assert_ron_snapshot!(vec![
+            transformed(&|cells| rotate(cells, &rect, Rotation::R90).unwrap()),
+            transformed(&|cells| rotate(cells, &rect, Rotation::R180).unwrap()),
+            transformed(&|cells| rotate(cells, &rect, Rotation::R270).unwrap()),
+            transformed(&|cells| {
+                flip(cells, &rect, true);
+                rect
+            }),
+            transformed(&|cells| {
+                flip(cells, &rect, false);
+                rect
+            }),
+        ], @r#"
+        [
+          (((10, 20), (11, 22)), [
+            (10, 20),
+            (10, 21),
+            (10, 22),
+            (11, 20),
+          ]),
+          (((10, 20), (12, 21)), [
+            (10, 20),
+            (10, 21),
+            (11, 21),
+            (12, 21),
+          ]),
+          (((10, 20), (11, 22)), [
+            (10, 22),
+            (11, 20),
+            (11, 21),
+            (11, 22),
+          ]),
+          (((10, 20), (12, 21)), [
+            (10, 20),
+            (10, 21),
+            (11, 20),
+            (12, 20),
+          ]),
+          (((10, 20), (12, 21)), [
+            (10, 21),
+            (11, 21),
+            (12, 20),
+            (12, 21),
+          ]),
+        ]
+        "#);
+    }
+}
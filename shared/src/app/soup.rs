@@ -0,0 +1,156 @@
+//! Random soups filling a rectangle, optionally symmetric like the soups of
+//! apgsearch
+//!
+//! Each cell is drawn in reading order unless an earlier cell it is an image
+//! of has already decided it, so a soup only depends on its rectangle,
+//! density, seed and symmetry.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{random::Random, selection::Rect, CellCoord, StateVector, ALIVE};
+
+/// most cells a soup may cover
+pub const MAX_CELLS: u64 = 1 << 22;
+
+/// Symmetry group of a soup around the centre of its rectangle. Images of a
+/// cell that fall outside the rectangle, or between cells, are left out.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Symmetry {
+    /// no symmetry
+    #[default]
+    C1,
+    /// half turns
+    C2,
+    /// quarter turns
+    C4,
+    /// mirrored left to right
+    D2,
+    /// mirrored left to right and top to bottom
+    D4,
+    /// quarter turns and mirrors
+    D8,
+}
+
+impl Symmetry {
+    /// images of a cell at `[row, col]` from the centre, in half cells
+    fn images(&self, [row, col]: [i64; 2]) -> Vec<[i64; 2]> {
+        let turns = [[row, col], [col, -row], [-row, -col], [-col, row]];
+        let mirror = |images: &[[i64; 2]]| -> Vec<[i64; 2]> {
+            images
+                .iter()
+                .flat_map(|&[r, c]| [[r, c], [r, -c]])
+                .collect()
+        };
+        match self {
+            Symmetry::C1 => vec![[row, col]],
+            Symmetry::C2 => vec![turns[0], turns[2]],
+            Symmetry::C4 => turns.to_vec(),
+            Symmetry::D2 => mirror(&turns[..1]),
+            Symmetry::D4 => mirror(&[turns[0], turns[2]]),
+            Symmetry::D8 => mirror(&turns),
+        }
+    }
+}
+
+/// states of every cell of `rect`, each alive with a chance of `density`
+pub fn fill(rect: &Rect, density: f32, seed: u64, symmetry: Symmetry) -> StateVector {
+    let mut random = Random::new(seed);
+    let [[top, left], [bottom, right]] = rect.corners();
+    // twice the centre, so turns about it stay on whole numbers, in i64 as
+    // it may lie past the largest coordinates
+    let centre = [
+        i64::from(top) + i64::from(bottom),
+        i64::from(left) + i64::from(right),
+    ];
+    let mut cells: HashMap<CellCoord, u8> = HashMap::new();
+    let mut soup = Vec::new();
+    for row in top..=bottom {
+        for col in left..=right {
+            let state = match cells.get(&[row, col]) {
+                Some(state) => *state,
+                None => {
+                    let state = match random.chance(density) {
+                        true => ALIVE,
+                        false => 0,
+                    };
+                    let from_centre = [
+                        2 * i64::from(row) - centre[0],
+                        2 * i64::from(col) - centre[1],
+                    ];
+                    for [r, c] in symmetry.images(from_centre) {
+                        let (r, c) = (r + centre[0], c + centre[1]);
+                        if r % 2 != 0 || c % 2 != 0 {
+                            continue;
+                        }
+                        let image = [i32::try_from(r / 2), i32::try_from(c / 2)];
+                        if let [Ok(r), Ok(c)] = image {
+                            if rect.contains(&[r, c]) {
+                                cells.insert([r, c], state);
+                            }
+                        }
+                    }
+                    state
+                }
+            };
+            soup.push(([row, col], state));
+        }
+    }
+    soup
+}
+
+#[cfg(test)]
+mod test_soup {
+    use super::*;
+
+    type Map<'a> = &'a dyn Fn(CellCoord) -> CellCoord;
+
+    fn alive(soup: &StateVector) -> Vec<CellCoord> {
+        soup.iter()
+            .filter(|(_, state)| *state == ALIVE)
+            .map(|(cell, _)| *cell)
+            .collect()
+    }
+
+    #[test]
+    fn test_symmetries() {
+        let square = Rect::from_corners([-3, 2], [4, 9]);
+        let odd = Rect::from_corners([0, 0], [6, 6]);
+        for rect in [square, odd] {
+            let [[top, left], [bottom, right]] = rect.corners();
+            let turn = |[r, c]: CellCoord| [top + c - left, right - (r - top)];
+            let half_turn = |[r, c]: CellCoord| [top + bottom - r, left + right - c];
+            let mirror = |[r, c]: CellCoord| [r, left + right - c];
+            let checks: [(Symmetry, &[Map]); 5] = [
+                (Symmetry::C2, &[&half_turn]),
+                (Symmetry::C4, &[&turn]),
+                (Symmetry::D2, &[&mirror]),
+                (Symmetry::D4, &[&mirror, &half_turn]),
+                (Symmetry::D8, &[&mirror, &turn]),
+            ];
+            for (symmetry, maps) in checks {
+                let soup = fill(&rect, 0.5, 3, symmetry);
+                let cells = alive(&soup);
+                assert!(!cells.is_empty());
+                for map in maps {
+                    let mut images: Vec<CellCoord> = cells.iter().map(|c| map(*c)).collect();
+                    images.sort();
+                    assert_eq!(images, cells, "{symmetry:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_density_and_seed() {
+        let rect = Rect::from_corners([0, 0], [15, 15]);
+        assert_eq!(fill(&rect, 0.0, 1, Symmetry::C1).len(), 256);
+        assert!(alive(&fill(&rect, 0.0, 1, Symmetry::C1)).is_empty());
+        assert_eq!(alive(&fill(&rect, 1.0, 1, Symmetry::D8)).len(), 256);
+        let soup = fill(&rect, 0.5, 1, Symmetry::C1);
+        assert_eq!(soup, fill(&rect, 0.5, 1, Symmetry::C1));
+        assert_ne!(soup, fill(&rect, 0.5, 2, Symmetry::C1));
+        let count = alive(&soup).len();
+        assert!((96..160).contains(&count), "{count}");
+    }
+}
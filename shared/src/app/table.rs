@@ -0,0 +1,645 @@
+//! Rules given as a table of transitions in Golly's `@RULE`/`@TABLE` format,
+//! such as the built in Wireworld
+//!
+//! Each transition lists the states the cell and its neighbours, clockwise
+//! from the north, may have and the state the cell turns into, e.g.
+//! `3,1,i,j,k,l,m,n,o,1` for a conductor next to one electron head. A
+//! variable used more than once in a transition has the same state
+//! everywhere, and the first transition that matches a cell wins, cells no
+//! transition matches keep their state.
+//!
+//! Loaded tables are kept in the [Tables] of the model, which rules naming
+//! them are parsed against, and shared with those rules. A world saved in a
+//! loaded rule carries its rule file after the pattern, to load it again.
+use std::{collections::HashSet, fmt::Display, sync::Arc};
+
+use lazy_static::lazy_static;
+
+use super::{adjacency::Adjacency, CellCoord, CellMap, Topology};
+
+/// Wireworld, where electrons made of a head (1) and a tail (2) run along
+/// conductors (3)
+const WIREWORLD: &str = "\
+@RULE Wireworld
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+# any state
+var a={0,1,2,3}
+var b=a
+var c=a
+var d=a
+var e=a
+var f=a
+var g=a
+var h=a
+# anything but an electron head
+var i={0,2,3}
+var j=i
+var k=i
+var l=i
+var m=i
+var n=i
+var o=i
+# heads become tails and tails conductors
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+# conductors next to one or two heads become heads
+3,1,i,j,k,l,m,n,o,1
+3,1,1,j,k,l,m,n,o,1
+@COLORS
+1 0 128 255
+2 255 64 64
+3 255 200 0
+";
+
+/// rule files known without loading them
+const BUILT_IN: [&str; 1] = [WIREWORLD];
+
+lazy_static! {
+    /// the built in rule files, parsed once
+    static ref BUILT_IN_TABLES: Vec<Arc<RuleTable>> = (BUILT_IN.iter())
+        .map(|text| Arc::new(RuleTable::parse(text).expect("built in tables are valid")))
+        .collect();
+}
+
+/// [row, column] of the neighbours in the order Golly lists them
+const MOORE: [CellCoord; 8] = [
+    [-1, 0],
+    [-1, 1],
+    [0, 1],
+    [1, 1],
+    [1, 0],
+    [1, -1],
+    [0, -1],
+    [-1, -1],
+];
+const VON_NEUMANN: [CellCoord; 4] = [[-1, 0], [0, 1], [1, 0], [0, -1]];
+
+/// most distinct variables in one transition
+const MAX_VARIABLES: usize = 10;
+/// state bound to each variable of a transition so far
+type Bindings = [Option<u8>; MAX_VARIABLES];
+
+/// Set of up to 256 states
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+struct States([u128; 2]);
+
+impl States {
+    const fn contains(&self, state: u8) -> bool {
+        self.0[state as usize / 128] & 1 << (state % 128) != 0
+    }
+    fn insert(&mut self, state: u8) {
+        self.0[state as usize / 128] |= 1 << (state % 128);
+    }
+}
+
+/// One position of a transition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Input {
+    states: States,
+    /// variable it is bound to
+    slot: Option<usize>,
+}
+
+impl Input {
+    /// `bindings` with `state` bound, none when the input does not allow it
+    fn bind(&self, state: u8, mut bindings: Bindings) -> Option<Bindings> {
+        if !self.states.contains(state) {
+            return None;
+        }
+        if let Some(slot) = self.slot {
+            match bindings[slot] {
+                Some(bound) if bound != state => return None,
+                _ => bindings[slot] = Some(state),
+            }
+        }
+        Some(bindings)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    State(u8),
+    /// state bound to a variable of the inputs
+    Variable(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    /// the cell, then its neighbours
+    inputs: Vec<Input>,
+    output: Output,
+}
+
+/// Neighbour orders a transition also applies to
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Symmetry {
+    /// the neighbour at `order[i]` takes the place of neighbour `i`
+    Orders(Vec<Vec<usize>>),
+    /// any order at all, the transition only counts neighbours
+    Permute,
+}
+
+impl Symmetry {
+    /// `name` for `count` neighbours listed clockwise
+    fn new(name: &str, count: usize) -> Option<Self> {
+        let turn = |by: usize| -> Vec<usize> { (0..count).map(|i| (i + by) % count).collect() };
+        let mirror = |order: Vec<usize>| -> Vec<usize> {
+            order.iter().map(|i| (count - i) % count).collect()
+        };
+        // turns by one neighbour are eighth turns for Moore and quarter turns
+        // for von Neumann
+        let quarter = count / 4;
+        let turns: Vec<Vec<usize>> = match name {
+            "none" | "reflect_horizontal" => vec![turn(0)],
+            "rotate4" | "rotate4reflect" => (0..4).map(|n| turn(n * quarter)).collect(),
+            "rotate8" | "rotate8reflect" if count == 8 => (0..8).map(turn).collect(),
+            "permute" => return Some(Symmetry::Permute),
+            _ => return None,
+        };
+        let orders = match name.ends_with("reflect") || name.starts_with("reflect") {
+            true => {
+                let mirrored: Vec<Vec<usize>> = turns.iter().cloned().map(mirror).collect();
+                turns.into_iter().chain(mirrored).collect()
+            }
+            false => turns,
+        };
+        Some(Symmetry::Orders(orders))
+    }
+}
+
+/// `inputs` matched to `neighbours` in any order
+fn permute(inputs: &[Input], neighbours: &[u8], bindings: Bindings) -> Option<Bindings> {
+    let Some((input, rest)) = inputs.split_first() else {
+        return Some(bindings);
+    };
+    (0..neighbours.len())
+        // neighbours in the same state match the same way
+        .filter(|&i| !neighbours[..i].contains(&neighbours[i]))
+        .find_map(|i| {
+            let bindings = input.bind(neighbours[i], bindings)?;
+            let mut others = neighbours.to_vec();
+            others.remove(i);
+            permute(rest, &others, bindings)
+        })
+}
+
+impl Transition {
+    /// new state of a cell in `state` with the `neighbours`, if it applies
+    fn apply(&self, state: u8, neighbours: &[u8], symmetry: &Symmetry) -> Option<u8> {
+        let (cell, inputs) = self.inputs.split_first()?;
+        let bindings = cell.bind(state, [None; MAX_VARIABLES])?;
+        let bindings = match symmetry {
+            Symmetry::Permute => permute(inputs, neighbours, bindings)?,
+            Symmetry::Orders(orders) => orders.iter().find_map(|order| {
+                (order.iter().zip(inputs)).try_fold(bindings, |bindings, (&i, input)| {
+                    input.bind(neighbours[i], bindings)
+                })
+            })?,
+        };
+        match self.output {
+            Output::State(state) => Some(state),
+            Output::Variable(slot) => bindings[slot],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleTable {
+    name: String,
+    /// the rule file, written after patterns saved in the rule
+    source: String,
+    /// count of cell states including dead
+    states: u8,
+    adjacency: Adjacency,
+    symmetry: Symmetry,
+    transitions: Vec<Transition>,
+    /// colour of each state set by the `@COLORS` section
+    colors: Vec<[u8; 3]>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// the file does not start with `@RULE` and a name
+    Name,
+    /// no `@TABLE` section
+    NoTable,
+    /// invalid line of a section
+    Line(String),
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::Name => write!(f, "expected a rule file starting with @RULE and a name"),
+            TableError::NoTable => write!(f, "only rules with a @TABLE section are supported"),
+            TableError::Line(line) => write!(f, "invalid rule table line {line:?}"),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+/// contents of a rule file rather than a world
+pub fn is_rule_file(data: &[u8]) -> bool {
+    let start = data.iter().position(|c| !c.is_ascii_whitespace());
+    start.map_or(false, |start| data[start..].starts_with(b"@RULE"))
+}
+
+/// the rule file on its own lines after a pattern, if there is one
+pub fn trailing_rule_file(text: &str) -> Option<&str> {
+    let start = text.find("\n@RULE")?;
+    Some(&text[start + 1..])
+}
+
+/// built in table called `name`, whatever its case
+pub fn built_in(name: &str) -> Option<Arc<RuleTable>> {
+    (BUILT_IN_TABLES.iter())
+        .find(|table| table.name.eq_ignore_ascii_case(name))
+        .cloned()
+}
+
+/// Rule tables loaded into a world, the latest last
+#[derive(Default)]
+pub struct Tables(Vec<Arc<RuleTable>>);
+
+impl Tables {
+    /// table called `name`, the latest loaded or else a built in one
+    pub fn find(&self, name: &str) -> Option<Arc<RuleTable>> {
+        let loaded = self.0.iter().rev().find(|table| table.name == name);
+        loaded.cloned().or_else(|| built_in(name))
+    }
+    /// parse a rule file and keep it for [Tables::find]
+    pub fn load(&mut self, text: &str) -> Result<Arc<RuleTable>, TableError> {
+        let table = RuleTable::parse(text)?;
+        if let Some(loaded) = self.0.iter().find(|loaded| ***loaded == table) {
+            return Ok(loaded.clone());
+        }
+        let table = Arc::new(table);
+        self.0.push(table.clone());
+        Ok(table)
+    }
+}
+
+/// every state up to `states`, or the states listed in `{1,2}` where listed
+/// variables stand for their states
+fn parse_states(text: &str, states: u8, variables: &[(String, States)]) -> Option<States> {
+    let mut set = States::default();
+    let items = match text
+        .strip_prefix('{')
+        .and_then(|text| text.strip_suffix('}'))
+    {
+        Some(items) => items,
+        None => text,
+    };
+    for item in items.split(',').map(str::trim) {
+        match item.parse::<u8>() {
+            Ok(state) if state < states => set.insert(state),
+            Ok(_) => return None,
+            Err(_) => {
+                let (_, states) = variables.iter().rev().find(|(name, _)| name == item)?;
+                (0..=255)
+                    .filter(|s| states.contains(*s))
+                    .for_each(|s| set.insert(s));
+            }
+        }
+    }
+    Some(set)
+}
+
+/// positions of a transition, split at commas outside of braces, or one per
+/// character when there are no commas
+fn split_transition(line: &str) -> Vec<&str> {
+    if !line.contains(',') {
+        return line
+            .split("")
+            .filter(|item| !item.trim().is_empty())
+            .collect();
+    }
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in line.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(line[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(line[start..].trim());
+    items
+}
+
+impl RuleTable {
+    fn parse(text: &str) -> Result<Self, TableError> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty());
+        let name = lines
+            .next()
+            .and_then(|line| line.strip_prefix("@RULE"))
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && !name.contains([':', '/', ' ']))
+            .ok_or(TableError::Name)?;
+        let mut table = Self {
+            name: name.to_string(),
+            source: text.trim().to_string(),
+            states: 2,
+            adjacency: Adjacency::Moore,
+            symmetry: Symmetry::Permute,
+            transitions: Vec::new(),
+            colors: Vec::new(),
+        };
+        let (mut section, mut has_table, mut symmetries) = ("", false, "none");
+        let mut variables: Vec<(String, States)> = Vec::new();
+        for line in lines {
+            let err = || TableError::Line(line.to_string());
+            if line.starts_with('@') {
+                section = line.split_whitespace().next().unwrap_or_default();
+                has_table |= section == "@TABLE";
+                continue;
+            }
+            match section {
+                "@TABLE" => {
+                    if let Some((key, value)) = line.split_once(':') {
+                        let value = value.trim();
+                        match key.trim() {
+                            "n_states" => match value.parse() {
+                                Ok(states) if states >= 2 => table.states = states,
+                                _ => return Err(err()),
+                            },
+                            "neighborhood" => {
+                                table.adjacency = match value {
+                                    "Moore" => Adjacency::Moore,
+                                    "vonNeumann" => Adjacency::VonNeumann,
+                                    _ => return Err(err()),
+                                }
+                            }
+                            "symmetries" => symmetries = value,
+                            _ => return Err(err()),
+                        }
+                    } else if let Some(variable) = line.strip_prefix("var ") {
+                        let (name, value) = variable.split_once('=').ok_or_else(err)?;
+                        let states =
+                            parse_states(value.trim(), table.states, &variables).ok_or_else(err)?;
+                        variables.push((name.trim().to_string(), states));
+                    } else {
+                        let transition = table.parse_transition(line, &variables);
+                        table.transitions.push(transition.ok_or_else(err)?);
+                    }
+                }
+                "@COLORS" => {
+                    let numbers: Option<Vec<u8>> =
+                        line.split_whitespace().map(|n| n.parse().ok()).collect();
+                    let Some(&[state, r, g, b]) = numbers.as_deref() else {
+                        return Err(err());
+                    };
+                    if state >= table.states {
+                        return Err(err());
+                    }
+                    let size = table.colors.len().max(state as usize + 1);
+                    table.colors.resize(size, [0, 0, 0]);
+                    table.colors[state as usize] = [r, g, b];
+                }
+                // icons and notes are for Golly
+                _ => (),
+            }
+        }
+        if !has_table {
+            return Err(TableError::NoTable);
+        }
+        let count = table.neighbours().len();
+        table.symmetry = Symmetry::new(symmetries, count)
+            .ok_or_else(|| TableError::Line(format!("symmetries:{symmetries}")))?;
+        Ok(table)
+    }
+
+    fn parse_transition(&self, line: &str, variables: &[(String, States)]) -> Option<Transition> {
+        let items = split_transition(line);
+        let (output, items) = items.split_last()?;
+        if items.len() != self.neighbours().len() + 1 {
+            return None;
+        }
+        let is_variable = |item: &str| variables.iter().any(|(name, _)| name == item);
+        // every variable used more than once, and the one given as output
+        let mut bound: Vec<&str> = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let repeated = items[i + 1..].contains(item) || output == item;
+            if is_variable(item) && repeated && !bound.contains(item) {
+                bound.push(item);
+            }
+        }
+        if bound.len() > MAX_VARIABLES {
+            return None;
+        }
+        let inputs = items
+            .iter()
+            .map(|item| {
+                Some(Input {
+                    states: parse_states(item, self.states, variables)?,
+                    slot: bound.iter().position(|name| name == item),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let output = match output.parse::<u8>() {
+            Ok(state) if state < self.states => Output::State(state),
+            Ok(_) => return None,
+            Err(_) => Output::Variable(bound.iter().position(|name| name == output)?),
+        };
+        Some(Transition { inputs, output })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+    /// one of the tables known without loading them
+    pub fn is_built_in(&self) -> bool {
+        built_in(&self.name).map_or(false, |table| *table == *self)
+    }
+    pub const fn states(&self) -> u8 {
+        self.states
+    }
+    pub const fn adjacency(&self) -> Adjacency {
+        self.adjacency
+    }
+    /// colour of each state from the `@COLORS` section, empty when it has none
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+    fn neighbours(&self) -> &'static [CellCoord] {
+        match self.adjacency {
+            Adjacency::VonNeumann => &VON_NEUMANN,
+            _ => &MOORE,
+        }
+    }
+    /// state after `state` with the `neighbours` in clockwise order
+    fn next(&self, state: u8, neighbours: &[u8]) -> u8 {
+        (self.transitions.iter())
+            .find_map(|transition| transition.apply(state, neighbours, &self.symmetry))
+            .unwrap_or(state)
+    }
+
+    /// the generation after `cells` in `topology`, dead cells with only dead
+    /// neighbours stay dead whatever the table says
+    pub fn tick(&self, cells: &CellMap, topology: Topology) -> CellMap {
+        let offsets = self.neighbours();
+        let state = |cell: CellCoord| {
+            topology
+                .wrap(cell)
+                .map_or(0, |cell| cells.get(&cell).copied().unwrap_or(0))
+        };
+        let mut candidates: HashSet<CellCoord> = cells.keys().copied().collect();
+        for [row, col] in cells.keys() {
+            candidates.extend(
+                (offsets.iter()).filter_map(|[dr, dc]| topology.wrap([row + dr, col + dc])),
+            );
+        }
+        let mut neighbours = Vec::with_capacity(offsets.len());
+        let mut next = CellMap::new();
+        for cell in candidates {
+            neighbours.clear();
+            neighbours.extend((offsets.iter()).map(|[dr, dc]| state([cell[0] + dr, cell[1] + dc])));
+            match self.next(state(cell), &neighbours) {
+                0 => (),
+                new => {
+                    next.insert(cell, new);
+                }
+            }
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod test_table {
+    use super::*;
+    use crate::app::{Life, Rule};
+
+    #[test]
+    /// an electron running along a wire
+    fn test_wireworld() {
+        let rule: Rule = "Wireworld".parse().unwrap();
+        assert_eq!(rule.states(), 4);
+        assert_eq!(rule.to_string(), "Wireworld");
+        assert_eq!(
+            "WireWorld:T20,20".parse::<Rule>().unwrap().to_string(),
+            "Wireworld:T20,20"
+        );
+        let mut life = Life::empty();
+        for col in 0..10 {
+            life.set_state([0, col], 3);
+        }
+        life.set_state([0, 1], 2);
+        life.set_state([0, 2], 1);
+        for _ in 0..5 {
+            life.tick(&rule);
+        }
+        let states: Vec<u8> = (0..10).map(|col| life.state_of(&[0, col])).collect();
+        assert_eq!(states, [3, 3, 3, 3, 3, 3, 2, 1, 3, 3]);
+    }
+
+    #[test]
+    fn test_table_format() {
+        let text = "@RULE Spread\n\
+            # a dead cell copies a lone live neighbour, or two of the same\n\
+            # state facing each other, and lone live cells die\n\
+            @TABLE\n\
+            n_states:3\n\
+            neighborhood:vonNeumann\n\
+            symmetries:rotate4\n\
+            var a={1,2}\n\
+            0,a,0,0,0,a\n\
+            0,a,0,a,0,a\n\
+            a00000\n\
+            @COLORS\n\
+            2 0 255 0\n";
+        assert!(is_rule_file(format!("\n{text}").as_bytes()));
+        let mut tables = Tables::default();
+        let table = tables.load(text).unwrap();
+        assert!(Arc::ptr_eq(&tables.load(text).unwrap(), &table));
+        assert_eq!(tables.find("Spread"), Some(table.clone()));
+        // each world keeps the tables it loaded
+        assert_eq!(Tables::default().find("Spread"), None);
+        assert!(Tables::default().find("wireworld").unwrap().is_built_in());
+        assert!(!table.is_built_in());
+        assert_eq!(table.colors(), [[0, 0, 0], [0, 0, 0], [0, 255, 0]]);
+        assert_eq!(table.next(0, &[0, 2, 0, 0]), 2);
+        assert_eq!(table.next(0, &[1, 0, 1, 0]), 1);
+        // `a` is the same state everywhere in a transition
+        assert_eq!(table.next(0, &[1, 0, 2, 0]), 0);
+        assert_eq!(table.next(2, &[0, 0, 0, 0]), 0);
+        // cells no transition matches keep their state
+        assert_eq!(table.next(2, &[1, 0, 0, 0]), 2);
+
+        let rule = Rule::from_table(table);
+        assert_eq!(rule.adjacency(), Adjacency::VonNeumann);
+        let mut life = Life::empty();
+        life.set_state([0, 0], 2);
+        life.tick(&rule);
+        let mut cells = life.cell_states();
+        cells.sort();
+        assert_eq!(
+            cells,
+            [([-1, 0], 2), ([0, -1], 2), ([0, 1], 2), ([1, 0], 2)]
+        );
+
+        for (invalid, err) in [
+            ("@TABLE\nn_states:2", TableError::Name),
+            ("@RULE X\n@TREE\nnum_states=2", TableError::NoTable),
+            (
+                "@RULE X\n@TABLE\n0,1,2",
+                TableError::Line("0,1,2".to_string()),
+            ),
+            (
+                "@RULE X\n@TABLE\nn_states:2\n0,0,0,0,0,0,0,0,0,2",
+                TableError::Line("0,0,0,0,0,0,0,0,0,2".to_string()),
+            ),
+            (
+                "@RULE X\n@TABLE\nsymmetries:rotate3",
+                TableError::Line("symmetries:rotate3".to_string()),
+            ),
+        ] {
+            assert_eq!(RuleTable::parse(invalid), Err(err), "{invalid}");
+        }
+    }
+
+    #[test]
+    /// symmetries apply the transitions to turned and mirrored neighbourhoods
+    fn test_symmetries() {
+        let table = |symmetries: &str| {
+            let text = format!(
+                "@RULE S\n@TABLE\nn_states:2\nsymmetries:{symmetries}\n0,1,1,0,1,0,0,0,0,1"
+            );
+            RuleTable::parse(&text).unwrap()
+        };
+        // N, NE and SE, turned a quarter, an eighth, and mirrored
+        let [quarter, eighth, mirrored] = [
+            [0, 0, 1, 1, 0, 1, 0, 0],
+            [0, 1, 1, 0, 1, 0, 0, 0],
+            [1, 0, 0, 0, 0, 1, 0, 1],
+        ];
+        for (symmetries, expected) in [
+            ("none", [false, false, false]),
+            ("rotate4", [true, false, false]),
+            ("rotate8", [true, true, false]),
+            ("reflect_horizontal", [false, false, true]),
+            ("rotate4reflect", [true, false, true]),
+            ("rotate8reflect", [true, true, true]),
+            ("permute", [true, true, true]),
+        ] {
+            let table = table(symmetries);
+            let born = [quarter, eighth, mirrored].map(|n| table.next(0, &n) == 1);
+            assert_eq!(born, expected, "{symmetries}");
+            assert_eq!(table.next(0, &[1, 1, 0, 1, 0, 0, 0, 0]), 1);
+        }
+    }
+}
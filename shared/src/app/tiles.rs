@@ -0,0 +1,222 @@
+//! Engine keeping the world as sparse 64x64 tiles of bits, stepping 64 cells
+//! of a row at once with bit sliced neighbour counts
+//!
+//! Only the tiles that changed in the last generation and their neighbours
+//! are stepped again, the others see the same neighbourhoods as before and
+//! stay as they are. It runs two state rules of the Moore neighbourhood that
+//! only count live neighbours, on an infinite plane.
+use std::collections::{HashMap, HashSet};
+
+use super::{adjacency::Adjacency, CellCoord, CellVector, Rule, Simulation, Topology};
+
+const SIZE: i32 = 64;
+
+/// row `i` has the cell in column `j` of the tile as bit `j`
+type Tile = [u64; SIZE as usize];
+
+/// [row, column] of a tile, the cells of tile `[1, 0]` start at row 64
+type TileCoord = [i32; 2];
+
+const EMPTY: Tile = [0; SIZE as usize];
+
+#[derive(Default)]
+pub struct Tiles {
+    /// tiles with live cells
+    tiles: HashMap<TileCoord, Tile>,
+    /// tiles that changed in the last generation or since they were loaded
+    changed: HashSet<TileCoord>,
+    /// rule the tiles were last stepped with
+    rule: Option<Rule>,
+}
+
+/// the rule only depends on the count of live neighbours and runs on a plane
+pub fn runs(rule: &Rule) -> bool {
+    rule.states() == 2
+        && rule.uses_transitions()
+        && rule.adjacency() == Adjacency::Moore
+        && rule.topology() == Topology::Plane
+        && counts(rule).is_some()
+}
+
+/// counts of live neighbours a cell is born and survives with, bit `n` for a
+/// count of `n`, none when the rule tells apart neighbourhoods of one count
+fn counts(rule: &Rule) -> Option<[u16; 2]> {
+    // counts for which each transition does not and does apply
+    let mut seen = [[0_u16; 2]; 2];
+    for neighbourhood in 0..=u8::MAX {
+        let count = 1 << neighbourhood.count_ones();
+        seen[0][usize::from(rule.birth(neighbourhood))] |= count;
+        seen[1][usize::from(rule.survive(neighbourhood))] |= count;
+    }
+    let [birth, survive] = seen;
+    // B0 rules are refused when parsed, cells with no live neighbours are
+    // never born
+    (birth[0] & birth[1] == 0 && survive[0] & survive[1] == 0)
+        .then_some([birth[1] & !1, survive[1]])
+}
+
+/// add `bits` to the count held one bit of it per plane, lowest first
+fn add(planes: &mut [u64; 4], bits: u64) {
+    let mut carry = bits;
+    for plane in planes.iter_mut() {
+        let next = *plane & carry;
+        *plane ^= carry;
+        carry = next;
+    }
+}
+
+impl Tiles {
+    /// row `row` of the tile at `key` and the last and first cells of the
+    /// rows on either side of it, rows -1 and 64 come from the tiles above
+    /// and below
+    fn row(&self, [tile_row, tile_col]: TileCoord, row: i32) -> [u64; 3] {
+        let tile_row = tile_row + row.div_euclid(SIZE);
+        let row = row.rem_euclid(SIZE) as usize;
+        [-1, 0, 1]
+            .map(|dc| (self.tiles.get(&[tile_row, tile_col + dc])).map_or(0, |tile| tile[row]))
+    }
+
+    /// the next generation of the tile at `key`
+    fn step_tile(&self, key: TileCoord, [birth, survive]: [u16; 2]) -> Tile {
+        // each row with the cells left and right of it shifted under it
+        let shifted = |row: i32| {
+            let [west, centre, east] = self.row(key, row);
+            [centre << 1 | west >> 63, centre, centre >> 1 | east << 63]
+        };
+        let mut next = EMPTY;
+        let (mut above, mut this) = (shifted(-1), shifted(0));
+        for (row, next) in (0..SIZE).zip(next.iter_mut()) {
+            let below = shifted(row + 1);
+            let mut planes = [0; 4];
+            for bits in above.iter().chain(&below).chain([&this[0], &this[2]]) {
+                add(&mut planes, *bits);
+            }
+            let count_is = |count: u32| {
+                (planes.iter().enumerate()).fold(!0, |mask, (i, plane)| match count >> i & 1 {
+                    1 => mask & plane,
+                    _ => mask & !plane,
+                })
+            };
+            let (mut born, mut survives) = (0, 0);
+            for count in 0..=8 {
+                if birth & 1 << count != 0 {
+                    born |= count_is(count);
+                }
+                if survive & 1 << count != 0 {
+                    survives |= count_is(count);
+                }
+            }
+            let alive = this[1];
+            *next = alive & survives | !alive & born;
+            (above, this) = (this, below);
+        }
+        next
+    }
+}
+
+impl Simulation for Tiles {
+    fn tick(&mut self, rule: &Rule) {
+        let Some(counts) = counts(rule) else {
+            return;
+        };
+        // other rules may change the tiles that stayed the same
+        if self.rule.as_ref() != Some(rule) {
+            self.changed = self.tiles.keys().copied().collect();
+            self.rule = Some(rule.clone());
+        }
+        let todo: HashSet<TileCoord> = (self.changed.iter())
+            .flat_map(|[row, col]| {
+                (-1..=1).flat_map(move |dr| (-1..=1).map(move |dc| [row + dr, col + dc]))
+            })
+            .collect();
+        let steps: Vec<(TileCoord, Tile)> = todo
+            .into_iter()
+            .map(|key| (key, self.step_tile(key, counts)))
+            .filter(|(key, next)| self.tiles.get(key).unwrap_or(&EMPTY) != next)
+            .collect();
+        self.changed = steps.iter().map(|(key, _)| *key).collect();
+        for (key, next) in steps {
+            match next == EMPTY {
+                true => self.tiles.remove(&key),
+                false => self.tiles.insert(key, next),
+            };
+        }
+    }
+    fn load(&mut self, cells: &[CellCoord]) {
+        self.tiles.clear();
+        for [row, col] in cells {
+            let key = [row.div_euclid(SIZE), col.div_euclid(SIZE)];
+            let tile = self.tiles.entry(key).or_insert(EMPTY);
+            tile[row.rem_euclid(SIZE) as usize] |= 1 << col.rem_euclid(SIZE);
+        }
+        self.changed = self.tiles.keys().copied().collect();
+    }
+    fn cells(&self) -> CellVector {
+        let mut cells = Vec::new();
+        for ([tile_row, tile_col], tile) in &self.tiles {
+            for (row, bits) in (tile_row * SIZE..).zip(tile) {
+                let cols = (0..SIZE).filter(|col| bits & 1 << col != 0);
+                cells.extend(cols.map(|col| [row, tile_col * SIZE + col]));
+            }
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod test_tiles {
+    use super::*;
+    use crate::app::{
+        selection::Rect,
+        soup::{self, Symmetry},
+        Life,
+    };
+
+    fn sorted(mut cells: CellVector) -> CellVector {
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn test_runs() {
+        for rule in ["B3/S23", "B36/S23", "B1/S8", "B2/S"] {
+            assert!(runs(&rule.parse().unwrap()), "{rule}");
+        }
+        for rule in ["B2n3/S23", "B3/S23H", "/2/3", "B3/S23:T10,10", "W30"] {
+            assert!(!runs(&rule.parse().unwrap()), "{rule}");
+        }
+    }
+
+    #[test]
+    /// soups of many rules crossing the edges of tiles step to the same
+    /// cells as the naive engine
+    fn test_against_naive() {
+        let rect = Rect::from_corners([-70, -80], [10, 20]);
+        for (seed, rule) in ["B3/S23", "B36/S23", "B3678/S34678", "B2/S", "B1/S8"]
+            .into_iter()
+            .enumerate()
+        {
+            let rule: Rule = rule.parse().unwrap();
+            let cells: CellVector = soup::fill(&rect, 0.4, seed as u64, Symmetry::C1)
+                .into_iter()
+                .filter(|(_, state)| *state != 0)
+                .map(|(cell, _)| cell)
+                .collect();
+            let mut life = Life::new(&cells);
+            let mut tiles = Tiles::default();
+            tiles.load(&cells);
+            for generation in 0..16 {
+                life.tick(&rule);
+                tiles.tick(&rule);
+                assert_eq!(
+                    sorted(tiles.cells()),
+                    sorted(life.state_as_list()),
+                    "{rule} generation {generation}"
+                );
+            }
+            life.step_pow(&rule, 2);
+            tiles.step_pow(&rule, 2);
+            assert_eq!(sorted(tiles.cells()), sorted(life.state_as_list()));
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Shape of the world, written after the rule like Golly does, e.g. `B3/S23:T40,30`
+//!
+//! Finite worlds are `width` columns by `height` rows with the origin in the
+//! middle, the top left cell is at [-(height / 2), -(width / 2)].
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::CellCoord;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Topology {
+    /// unbounded in every direction
+    #[default]
+    Plane,
+    /// `:P`, cells beyond the edges are always dead
+    Bounded { width: u32, height: u32 },
+    /// `:T`, leaving one edge enters at the opposite one
+    Torus { width: u32, height: u32 },
+    /// `:K`, like a torus but crossing the top or bottom edge mirrors left and right
+    KleinBottle { width: u32, height: u32 },
+    /// `:C`, crossing any edge mirrors along it
+    CrossSurface { width: u32, height: u32 },
+}
+
+/// largest width or height of a finite world
+pub const MAX_SIZE: u32 = 1 << 30;
+
+/// `coord` folded into `start..start + size`, and whether it crossed the edges
+/// an odd number of times
+const fn fold(coord: i32, start: i32, size: i32) -> (i32, bool) {
+    let offset = coord - start;
+    (
+        start + offset.rem_euclid(size),
+        offset.div_euclid(size) % 2 != 0,
+    )
+}
+
+/// `coord` of a cell in `start..start + size` seen from the other side
+const fn mirror(coord: i32, start: i32, size: i32) -> i32 {
+    2 * start + size - 1 - coord
+}
+
+impl Topology {
+    /// [height, width] of a finite world
+    pub const fn size(&self) -> Option<[u32; 2]> {
+        match *self {
+            Topology::Plane => None,
+            Topology::Bounded { width, height }
+            | Topology::Torus { width, height }
+            | Topology::KleinBottle { width, height }
+            | Topology::CrossSurface { width, height } => Some([height, width]),
+        }
+    }
+    /// top left and bottom right cells of a finite world
+    pub const fn corners(&self) -> Option<[CellCoord; 2]> {
+        let Some([height, width]) = self.size() else {
+            return None;
+        };
+        let (height, width) = (height as i32, width as i32);
+        let top_left = [-(height / 2), -(width / 2)];
+        Some([
+            top_left,
+            [top_left[0] + height - 1, top_left[1] + width - 1],
+        ])
+    }
+
+    /// finite worlds are between 1 and [MAX_SIZE] cells across
+    pub fn is_valid(&self) -> bool {
+        self.size()
+            .map_or(true, |size| size.iter().all(|n| (1..=MAX_SIZE).contains(n)))
+    }
+    /// the cell that `cell` stands for, none when it is past a bounded edge
+    pub fn wrap(&self, cell: CellCoord) -> Option<CellCoord> {
+        let Some([height, width]) = self.size() else {
+            return Some(cell);
+        };
+        let [[top, left], _] = self.corners()?;
+        let (height, width) = (height as i32, width as i32);
+        let (row, crossed_row) = fold(cell[0], top, height);
+        let (col, crossed_col) = fold(cell[1], left, width);
+        match self {
+            Topology::Plane => Some(cell),
+            Topology::Bounded { .. } if [row, col] != cell => None,
+            Topology::Bounded { .. } | Topology::Torus { .. } => Some([row, col]),
+            Topology::KleinBottle { .. } => match crossed_row {
+                true => Some([row, mirror(col, left, width)]),
+                false => Some([row, col]),
+            },
+            Topology::CrossSurface { .. } => {
+                let row = if crossed_col {
+                    mirror(row, top, height)
+                } else {
+                    row
+                };
+                let col = if crossed_row {
+                    mirror(col, left, width)
+                } else {
+                    col
+                };
+                Some([row, col])
+            }
+        }
+    }
+
+    /// where `cell` shows up in the copy of the world `tiles` [down, right] of it
+    pub fn image(&self, cell: CellCoord, tiles: [i32; 2]) -> Option<CellCoord> {
+        let [[top, left], _] = self.corners()?;
+        let [height, width] = self.size()?.map(|n| n as i32);
+        let (odd_row, odd_col) = (tiles[0] % 2 != 0, tiles[1] % 2 != 0);
+        let [row, col] = match self {
+            Topology::Plane | Topology::Bounded { .. } => return None,
+            Topology::Torus { .. } => cell,
+            Topology::KleinBottle { .. } if odd_row => [cell[0], mirror(cell[1], left, width)],
+            Topology::KleinBottle { .. } => cell,
+            Topology::CrossSurface { .. } => [
+                if odd_col {
+                    mirror(cell[0], top, height)
+                } else {
+                    cell[0]
+                },
+                if odd_row {
+                    mirror(cell[1], left, width)
+                } else {
+                    cell[1]
+                },
+            ],
+        };
+        Some([row + tiles[0] * height, col + tiles[1] * width])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopologyError(pub String);
+
+impl Display for TopologyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected a topology like T40,30 or K40*,30 up to {MAX_SIZE} wide, got {:?}",
+            self.0
+        )
+    }
+}
+
+impl FromStr for Topology {
+    type Err = TopologyError;
+
+    /// `T40,30` or `T40` for a square, `K40*,30` marks the twisted edges
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TopologyError(s.to_string());
+        let mut chars = s.trim().chars();
+        let kind = chars.next().ok_or_else(err)?.to_ascii_uppercase();
+        let size = chars.as_str();
+        let (width, height) = size.split_once(',').unwrap_or((size, size));
+        let width = match kind {
+            'K' => width.strip_suffix('*').unwrap_or(width),
+            _ => width,
+        };
+        let parse = |n: &str| match n.trim().parse::<u32>() {
+            Ok(n) if (1..=MAX_SIZE).contains(&n) => Ok(n),
+            _ => Err(err()),
+        };
+        let (width, height) = (parse(width)?, parse(height)?);
+        match kind {
+            'P' => Ok(Topology::Bounded { width, height }),
+            'T' => Ok(Topology::Torus { width, height }),
+            'K' => Ok(Topology::KleinBottle { width, height }),
+            'C' => Ok(Topology::CrossSurface { width, height }),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Topology::Plane => Ok(()),
+            Topology::Bounded { width, height } => write!(f, "P{width},{height}"),
+            Topology::Torus { width, height } => write!(f, "T{width},{height}"),
+            Topology::KleinBottle { width, height } => write!(f, "K{width}*,{height}"),
+            Topology::CrossSurface { width, height } => write!(f, "C{width},{height}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_topology {
+    use super::*;
+
+    #[test]
+    fn test_notation() {
+        assert_eq!(
+            "T40,30".parse(),
+            Ok(Topology::Torus {
+                width: 40,
+                height: 30
+            })
+        );
+        assert_eq!(
+            "p8".parse(),
+            Ok(Topology::Bounded {
+                width: 8,
+                height: 8
+            })
+        );
+        for text in ["P5,4", "T5,4", "K5*,4", "C5,4"] {
+            assert_eq!(text.parse::<Topology>().unwrap().to_string(), text);
+        }
+        assert!("T0,4".parse::<Topology>().is_err());
+        assert!("X5,4".parse::<Topology>().is_err());
+        assert!("T5,".parse::<Topology>().is_err());
+        assert!("".parse::<Topology>().is_err());
+        assert!(!Topology::Torus {
+            width: 0,
+            height: 4
+        }
+        .is_valid());
+    }
+
+    #[test]
+    /// edges of a 5 wide, 4 high world, which spans rows -2..=1 and columns -2..=2
+    fn test_wrap() {
+        let (width, height) = (5, 4);
+        let bounded = Topology::Bounded { width, height };
+        let torus = Topology::Torus { width, height };
+        let klein = Topology::KleinBottle { width, height };
+        let cross = Topology::CrossSurface { width, height };
+        assert_eq!(torus.corners(), Some([[-2, -2], [1, 2]]));
+
+        assert_eq!(bounded.wrap([1, 2]), Some([1, 2]));
+        assert_eq!(bounded.wrap([2, 2]), None);
+        assert_eq!(Topology::Plane.wrap([99, -99]), Some([99, -99]));
+
+        // off the right edge
+        assert_eq!(torus.wrap([-2, 3]), Some([-2, -2]));
+        assert_eq!(klein.wrap([-2, 3]), Some([-2, -2]));
+        assert_eq!(cross.wrap([-2, 3]), Some([1, -2]));
+        // off the bottom edge
+        assert_eq!(torus.wrap([2, -2]), Some([-2, -2]));
+        assert_eq!(klein.wrap([2, -2]), Some([-2, 2]));
+        assert_eq!(cross.wrap([2, -2]), Some([-2, 2]));
+        // off the top left corner
+        assert_eq!(torus.wrap([-3, -3]), Some([1, 2]));
+        assert_eq!(klein.wrap([-3, -3]), Some([1, -2]));
+    }
+
+    #[test]
+    /// a cell next to a copy of the world wraps back to its neighbour in the world
+    fn test_images() {
+        let (width, height) = (5, 4);
+        for topology in [
+            Topology::Torus { width, height },
+            Topology::KleinBottle { width, height },
+            Topology::CrossSurface { width, height },
+        ] {
+            for tiles in [[0, 1], [1, 0], [-1, -1], [2, 3]] {
+                for cell in [[-2, -2], [0, 1], [1, 2]] {
+                    let image = topology.image(cell, tiles).unwrap();
+                    assert_eq!(topology.wrap(image), Some(cell), "{topology:?} {tiles:?}");
+                }
+            }
+        }
+        assert_eq!(Topology::Plane.image([0, 0], [0, 1]), None);
+    }
+}
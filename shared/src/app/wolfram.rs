@@ -0,0 +1,201 @@
+//! One dimensional rules drawn as a space-time diagram, Wolfram's elementary
+//! rules such as rule 30, `W30`, and totalistic ones such as `W10T`
+//!
+//! A generation is one row of the world and each step writes the next one
+//! below it. An elementary rule reads the cell and its left and right
+//! neighbours as a 3 bit number, left first, and the cell is alive when that
+//! bit of the rule number is set. A totalistic rule `W{code}T{range}` counts
+//! the live cells up to `range` away instead, the cell included, and checks
+//! that bit of `code`.
+//!
+//! Rules with bit 0 set bring empty space alive, so they need a world of a
+//! finite width. Finite worlds scroll up once the diagram reaches the bottom.
+use std::collections::HashSet;
+
+use super::Topology;
+
+/// most cells counted on each side by a totalistic rule
+pub const MAX_RANGE: u8 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wolfram {
+    /// bit `i` is the next state for the neighbourhood or count `i`
+    code: u32,
+    /// cells counted on each side, 1 for elementary rules
+    range: u8,
+    totalistic: bool,
+}
+
+impl Wolfram {
+    /// `W30`, or `W10T` and `W10T2` for totalistic rules of range 1 and 2
+    pub fn parse(rulestring: &str) -> Option<Self> {
+        let mut chars = rulestring.trim().chars();
+        if !chars.next()?.eq_ignore_ascii_case(&'W') {
+            return None;
+        }
+        let text = chars.as_str();
+        let (code, totalistic, range) = match text.find(['T', 't']) {
+            Some(t) => {
+                let range = match &text[t + 1..] {
+                    "" => 1,
+                    range => range.parse().ok()?,
+                };
+                (&text[..t], true, range)
+            }
+            None => (text, false, 1),
+        };
+        if !code.starts_with(|c: char| c.is_ascii_digit()) || !(1..=MAX_RANGE).contains(&range) {
+            return None;
+        }
+        let rule = Self {
+            code: code.parse().ok()?,
+            range,
+            totalistic,
+        };
+        (u64::from(rule.code) < 1 << rule.outcomes()).then_some(rule)
+    }
+
+    pub fn write(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "W{}", self.code)?;
+        match (self.totalistic, self.range) {
+            (false, _) => Ok(()),
+            (true, 1) => write!(f, "T"),
+            (true, range) => write!(f, "T{range}"),
+        }
+    }
+
+    /// count of neighbourhoods or live cell counts the code has a bit for
+    const fn outcomes(&self) -> u32 {
+        match self.totalistic {
+            true => 2 * self.range as u32 + 2,
+            false => 8,
+        }
+    }
+    /// a cell with no live cells above it comes alive
+    pub const fn fills_space(&self) -> bool {
+        self.code & 1 != 0
+    }
+
+    /// live columns of the generation after the one with the `live` columns
+    pub fn next_row(&self, live: &HashSet<i32>, topology: Topology) -> Vec<i32> {
+        let range = i32::from(self.range);
+        let corners = topology.corners();
+        let alive = |col: i32| match corners {
+            // only the columns of a wrapping world matter here
+            Some([[top, _], _]) => topology
+                .wrap([top, col])
+                .map_or(false, |[_, col]| live.contains(&col)),
+            None => live.contains(&col),
+        };
+        let columns: HashSet<i32> = match corners {
+            Some([[_, left], [_, right]]) => (left..=right).collect(),
+            None => live
+                .iter()
+                .flat_map(|col| col - range..=col + range)
+                .collect(),
+        };
+        let mut next: Vec<i32> = columns
+            .into_iter()
+            .filter(|col| {
+                let cells = (col - range..=col + range).map(alive);
+                let outcome = match self.totalistic {
+                    true => cells.filter(|alive| *alive).count() as u32,
+                    false => cells.fold(0, |index, alive| index << 1 | u32::from(alive)),
+                };
+                self.code & 1 << outcome != 0
+            })
+            .collect();
+        next.sort_unstable();
+        next
+    }
+}
+
+#[cfg(test)]
+mod test_wolfram {
+    use super::*;
+    use crate::app::{Life, Rule};
+
+    /// rows of the diagram as text, `o` for live cells
+    fn diagram(life: &Life, rows: std::ops::RangeInclusive<i32>, cols: i32) -> Vec<String> {
+        rows.map(|row| {
+            (-cols..=cols)
+                .map(|col| match life.state_of(&[row, col]) {
+                    0 => '.',
+                    _ => 'o',
+                })
+                .collect()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_notation() {
+        for rulestring in ["W30", "W110", "W10T", "W100T3"] {
+            assert_eq!(rulestring.parse::<Rule>().unwrap().to_string(), rulestring);
+        }
+        assert_eq!("w6t1".parse::<Rule>().unwrap().to_string(), "W6T");
+        assert!(Wolfram::parse("W9T").unwrap().fills_space());
+        assert!(!Wolfram::parse("W30").unwrap().fills_space());
+        assert_eq!(
+            "W1:T20,10".parse::<Rule>().unwrap().to_string(),
+            "W1:T20,10"
+        );
+        for invalid in ["W256", "W16T", "W1T0", "W1T8", "W", "Wx", "W1"] {
+            assert!(invalid.parse::<Rule>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    /// rule 90 draws a Sierpinski triangle below one live cell
+    fn test_elementary() {
+        let rule: Rule = "W90".parse().unwrap();
+        let mut life = Life::new(&[[0, 0]]);
+        for _ in 0..3 {
+            life.tick(&rule);
+        }
+        assert_eq!(
+            diagram(&life, 0..=3, 4),
+            ["....o....", "...o.o...", "..o...o..", ".o.o.o.o."]
+        );
+        // rule 30 grows to the left in its irregular way
+        let rule: Rule = "W30".parse().unwrap();
+        let mut life = Life::new(&[[0, 0]]);
+        for _ in 0..3 {
+            life.tick(&rule);
+        }
+        assert_eq!(
+            diagram(&life, 0..=3, 4),
+            ["....o....", "...ooo...", "..oo..o..", ".oo.oooo."]
+        );
+    }
+
+    #[test]
+    /// a totalistic rule alive for 1 or 2 of the 3 cells, and rule 1 which
+    /// turns rows with no live cells full on a torus that scrolls
+    fn test_totalistic_and_finite() {
+        let rule: Rule = "W6T".parse().unwrap();
+        let mut life = Life::new(&[[0, 0]]);
+        life.tick(&rule);
+        life.tick(&rule);
+        assert_eq!(diagram(&life, 0..=2, 3), ["...o...", "..ooo..", ".oo.oo."]);
+
+        // a 5 wide, 3 high world spans rows -1..=1 and columns -2..=2
+        let rule: Rule = "W1:T5,3".parse().unwrap();
+        let mut life = Life::new(&[[-1, -2], [-1, -1], [-1, 0], [-1, 1], [-1, 2]]);
+        let mut rows = Vec::new();
+        for _ in 0..4 {
+            life.tick(&rule);
+            rows.push(diagram(&life, -1..=1, 2).join(" "));
+        }
+        assert_eq!(
+            rows,
+            [
+                "ooooo ..... .....",
+                "ooooo ..... ooooo",
+                "..... ooooo .....",
+                "ooooo ..... ooooo",
+            ]
+        );
+        assert!("W1".parse::<Rule>().is_err());
+    }
+}
@@ -30,3 +30,9 @@ pub fn handle_response(id: u32, data: &[u8]) -> Vec<u8> {
 pub fn view() -> Vec<u8> {
     CORE.view()
 }
+
+/// the pattern catalog, which is left out of the view model
+#[wasm_bindgen]
+pub fn patterns() -> Vec<u8> {
+    bincode::serialize(catalog()).expect("the catalog serializes")
+}
@@ -1,5 +1,7 @@
 use crux_core::typegen::TypeGen;
-use shared::App;
+use shared::{
+    App, Category, Engine, FileFormat, Layout, PatternInfo, Rotation, Symmetry, Topology,
+};
 use std::path::PathBuf;
 
 fn main() -> anyhow::Result<()> {
@@ -8,6 +10,14 @@ fn main() -> anyhow::Result<()> {
     let mut gen = TypeGen::new();
 
     gen.register_app::<App>()?;
+    gen.register_type::<Engine>()?;
+    gen.register_type::<FileFormat>()?;
+    gen.register_type::<Rotation>()?;
+    gen.register_type::<PatternInfo>()?;
+    gen.register_type::<Category>()?;
+    gen.register_type::<Topology>()?;
+    gen.register_type::<Layout>()?;
+    gen.register_type::<Symmetry>()?;
 
     let output_root = PathBuf::from("./generated");
 
@@ -26,6 +26,7 @@ use leptos_use::use_window;
 use leptos_use::UseElementSizeReturn;
 use leptos_use::UseTimeoutFnReturn;
 use log::trace;
+use shared::AlertOpereation;
 use shared::ExportOperation;
 use shared::Vec2;
 use wasm_bindgen::convert::IntoWasmAbi;
@@ -38,13 +39,17 @@ use codee::string::{FromToStringCodec, JsonSerdeCodec};
 
 use leptos::mount::mount_to_body;
 // use leptos_use::docs::{demo_or_body, BooleanDisplay};
-use leptos_use::use_interval;
-use leptos_use::UseIntervalReturn;
 use leptos_use::{use_window_size, UseWindowSizeReturn};
 
 use leptos::html;
 
+use shared::Chances;
+use shared::Engine;
 use shared::Event;
+use shared::FileFormat;
+use shared::Layout;
+use shared::Rotation;
+use shared::Symmetry;
 
 use wasm_bindgen::prelude::*;
 use web_sys::CanvasRenderingContext2d;
@@ -71,6 +76,7 @@ enum NoticeKind {
     #[default]
     Hidden,
     Success,
+    Warning,
     Error,
 }
 
@@ -79,6 +85,7 @@ impl Display for NoticeKind {
         let txt = match self {
             NoticeKind::Hidden => "is-hidden",
             NoticeKind::Success => "is-success",
+            NoticeKind::Warning => "is-warning",
             NoticeKind::Error => "is-danger",
         };
         write!(f, "{txt}")
@@ -248,7 +255,8 @@ fn GameCanvas(
             // let camx = camera_pos.get()[0] as f64 + width / 2.0;
             // let camy = camera_pos.get()[1] as f64 + height / 2.0;
 
-            let draw_grid = cell_size > 13.0;
+            let hexagons = view.layout == Layout::Hexagonal;
+            let draw_grid = cell_size > 13.0 && !hexagons;
             if draw_grid {
                 ctx.set_line_width(2.0);
                 let mut x = view.modx as f64;
@@ -268,10 +276,27 @@ fn GameCanvas(
             } else {
                 // ctx.set_line_width(0.0);
             }
-            for [x, y] in &view.cell_coords {
+            // pointy topped hexagon in the box at x, y
+            let hexagon = |x: f64, y: f64| {
+                let height = cell_size * 2.0 / 3f64.sqrt();
+                ctx.move_to(x + cell_size / 2.0, y);
+                ctx.line_to(x + cell_size, y + height / 4.0);
+                ctx.line_to(x + cell_size, y + height * 3.0 / 4.0);
+                ctx.line_to(x + cell_size / 2.0, y + height);
+                ctx.line_to(x, y + height * 3.0 / 4.0);
+                ctx.line_to(x, y + height / 4.0);
+                ctx.close_path();
+            };
+            let cells = view.cell_coords.iter().zip(&view.cell_states);
+            let palette = !view.palette.is_empty();
+            for ([x, y], _) in cells.clone().filter(|(_, state)| **state == 1 && !palette) {
                 let x = *x as f64;
                 let y = *y as f64;
-                ctx.rect(x, y, cell_size, cell_size);
+                if hexagons {
+                    hexagon(x, y);
+                } else {
+                    ctx.rect(x, y, cell_size, cell_size);
+                }
             }
 
             // }
@@ -279,10 +304,42 @@ fn GameCanvas(
             if draw_grid {
                 ctx.stroke();
             }
+            // decaying cells of generations rules fade as they age, rule
+            // tables may colour each state
+            for ([x, y], state) in cells.filter(|(_, state)| **state > 1 || palette) {
+                let fill = match view.palette.get(*state as usize) {
+                    Some([r, g, b]) => format!("rgb({r}, {g}, {b})"),
+                    None if *state == 1 => "red".to_string(),
+                    None => {
+                        let lightness = (40 + 5 * *state as u32).min(90);
+                        format!("hsl(0, 100%, {lightness}%)")
+                    }
+                };
+                ctx.set_fill_style_str(&fill);
+                if hexagons {
+                    ctx.begin_path();
+                    hexagon(*x as f64, *y as f64);
+                    ctx.fill();
+                } else {
+                    ctx.fill_rect(*x as f64, *y as f64, cell_size, cell_size);
+                }
+            }
+            if let Some([[x0, y0], [x1, y1]]) = view.world_box {
+                ctx.set_stroke_style_str("hsl(48, 100%, 67%)");
+                ctx.set_line_width(3.0);
+                ctx.stroke_rect(x0 as f64, y0 as f64, (x1 - x0) as f64, (y1 - y0) as f64);
+            }
+            if let Some([[x0, y0], [x1, y1]]) = view.selection_screen {
+                ctx.set_stroke_style_str("hsl(171, 100%, 41%)");
+                ctx.set_line_width(3.0);
+                ctx.stroke_rect(x0 as f64, y0 as f64, (x1 - x0) as f64, (y1 - y0) as f64);
+            }
         }
     });
 
     let (short_press, set_short_press) = signal(false);
+    // shift dragging selects instead of panning
+    let (select_anchor, set_select_anchor) = signal(None::<[f32; 2]>);
 
     let click_handler = move |location: [f32; 2]| {
         info!("location: {:?}", location);
@@ -293,12 +350,20 @@ fn GameCanvas(
 
     let handle_pointerup = move |ev: PointerEvent| {
         if !is_touch {
+            if select_anchor.get().is_some() {
+                set_select_anchor.set(None);
+                return;
+            }
             click_handler([ev.offset_x() as f32, ev.offset_y() as f32]);
             set_drag_start.set(false);
         }
     };
     let handle_pointerdown = move |ev: PointerEvent| {
-        if !is_touch {
+        if !is_touch && ev.shift_key() {
+            let pos = [ev.offset_x() as f32, ev.offset_y() as f32];
+            set_select_anchor.set(Some(pos));
+            set_event.set(Event::SelectScreenRect(pos, pos));
+        } else if !is_touch {
             set_short_press.set(true);
             set_timeout(
                 move || set_short_press.set(false),
@@ -311,7 +376,10 @@ fn GameCanvas(
         }
     };
     let handle_pointermove = move |ev: PointerEvent| {
-        if !is_touch {
+        if let Some(anchor) = select_anchor.get() {
+            let pos = [ev.offset_x() as f32, ev.offset_y() as f32];
+            set_event.set(Event::SelectScreenRect(anchor, pos));
+        } else if !is_touch {
             set_zoom.set(1.0);
             set_drag_end.set([ev.offset_x() as f32, ev.offset_y() as f32]);
         }
@@ -375,6 +443,92 @@ fn GameCanvas(
     }
 }
 
+/// signals the core effects write to
+#[derive(Clone, Copy)]
+struct Shell {
+    set_view: WriteSignal<shared::ViewModel>,
+    set_notice: WriteSignal<Notice>,
+    set_show_menu: WriteSignal<bool>,
+    export_node: NodeRef<html::A>,
+}
+
+fn process_effects(core: &core::Core, effects: Vec<shared::Effect>, shell: Shell) {
+    let Shell {
+        set_view,
+        set_notice,
+        set_show_menu,
+        export_node,
+    } = shell;
+    for effect in effects {
+        match effect {
+            shared::Effect::Alert(req) => {
+                let (msg, kind) = match req.operation {
+                    AlertOpereation::Info(msg) => (msg, NoticeKind::Success),
+                    AlertOpereation::Warning(msg) => (msg, NoticeKind::Warning),
+                    AlertOpereation::Error(msg) => (msg, NoticeKind::Error),
+                };
+                set_notice.set(Notice { msg, kind });
+            }
+            shared::Effect::FileIO(req) => {
+                let op: ExportOperation = req.operation;
+                match op {
+                    ExportOperation::Copy(data) => {
+                        let clipboard = web_sys::window().unwrap().navigator().clipboard();
+
+                        if clipboard.is_undefined() {
+                            let kind = NoticeKind::Error;
+                            set_show_menu.set(false);
+                            set_notice.set(Notice {
+                                msg: "Access to clipboard was denied".to_string(),
+                                kind,
+                            });
+                            continue;
+                        };
+                        if let Ok(txt) = std::str::from_utf8(&data) {
+                            let _promise = clipboard.write_text(txt);
+                            let msg =
+                                format!("Copied: {} ...", txt.chars().take(30).collect::<String>());
+                            let kind = NoticeKind::Success;
+                            set_show_menu.set(false);
+                            set_notice.set(Notice { msg, kind });
+                        } else {
+                            let msg = format!("failed to get world data as txt");
+                            let kind = NoticeKind::Success;
+                            set_show_menu.set(false);
+                            set_notice.set(Notice { msg, kind });
+                            log::error!("failed to parsing world data");
+                        }
+                    }
+                    ExportOperation::Save(file_name, data) => {
+                        let link = export_node
+                            .get()
+                            .expect("The Anchor must exist to preform the file save");
+                        let blob = gloo_file::Blob::new(data.as_slice());
+                        let url = gloo_file::ObjectUrl::from(blob);
+                        link.set_attribute("href", &url).unwrap();
+                        link.set_attribute("download", &file_name).unwrap();
+                        // link.query_selector
+                        let click_event: web_sys::Event = MouseEvent::new("click").unwrap().into();
+                        link.dispatch_event(&click_event).unwrap();
+                    }
+                };
+            }
+            shared::Effect::Render(_) => set_view.set(core.view()),
+            shared::Effect::Timer(mut req) => {
+                let core = core.clone();
+                let delay = Duration::from_millis(req.operation.millis);
+                set_timeout(
+                    move || {
+                        let effects = core.resolve(&mut req, ());
+                        process_effects(&core, effects, shell);
+                    },
+                    delay,
+                );
+            }
+        }
+    }
+}
+
 #[component]
 fn root_component() -> impl IntoView {
     let core = core::new();
@@ -384,25 +538,13 @@ fn root_component() -> impl IntoView {
     let (view, set_view) = signal(core.view());
     let view = Memo::new(move |_| view.get());
 
-    let (running, set_run) = signal(false);
-    let millis = 15;
-    let UseIntervalReturn {
-        counter,
-        pause,
-        resume,
-        ..
-    } = use_interval(millis);
-
-    let _timer = Effect::new(move || if running.get() { resume() } else { pause() });
-
-    let _time_stepper = Effect::watch(
-        move || counter.get(),
-        move |_, _, _| set_event.set(Event::Step),
-        false,
-    );
+    let running = move || view.get().running;
 
     let export_node = NodeRef::<html::A>::new();
     let (show_menu, set_show_menu) = signal(false);
+    let (soup_density, set_soup_density) = signal(0.5_f32);
+    let (soup_symmetry, set_soup_symmetry) = signal(Symmetry::C1);
+    let (census_gap, set_census_gap) = signal(1_u32);
     let (notice, set_notice) = signal(<Notice>::default());
 
     let close_notice = move || set_notice.update(|n| n.kind = NoticeKind::Hidden);
@@ -412,66 +554,27 @@ fn root_component() -> impl IntoView {
         ..
     } = use_timeout_fn(move |()| close_notice(), 5000.0);
 
+    let _notice_timer = Effect::watch(
+        move || notice.get().kind,
+        move |kind, _, _| {
+            if *kind != NoticeKind::Hidden {
+                start_notice_timer(());
+            }
+        },
+        false,
+    );
+
+    let shell = Shell {
+        set_view,
+        set_notice,
+        set_show_menu,
+        export_node,
+    };
     let _event_processor = Effect::new(move || {
         let event = event.get();
         trace!("got event: {:#?}", event);
         let effects = core.process_event(event);
-        for effect in effects {
-            match effect {
-                shared::Effect::Alert(_) => todo!(),
-                shared::Effect::FileIO(req) => {
-                    let op: ExportOperation = req.operation;
-                    match op {
-                        ExportOperation::Copy(data) => {
-                            let clipboard = web_sys::window().unwrap().navigator().clipboard();
-
-                            if clipboard.is_undefined() {
-                                let kind = NoticeKind::Error;
-                                set_show_menu.set(false);
-                                set_notice.set(Notice {
-                                    msg: "Access to clipboard was denied".to_string(),
-                                    kind,
-                                });
-                                start_notice_timer(());
-                                return;
-                            };
-                            if let Ok(txt) = std::str::from_utf8(&data) {
-                                let _promise = clipboard.write_text(txt);
-                                let msg = format!(
-                                    "Copied: {} ...",
-                                    txt.chars().take(30).collect::<String>()
-                                );
-                                let kind = NoticeKind::Success;
-                                set_show_menu.set(false);
-                                set_notice.set(Notice { msg, kind });
-                                start_notice_timer(());
-                            } else {
-                                let msg = format!("failed to get world data as txt");
-                                let kind = NoticeKind::Success;
-                                set_show_menu.set(false);
-                                set_notice.set(Notice { msg, kind });
-                                log::error!("failed to parsing world data");
-                            }
-                        }
-                        ExportOperation::Save(data) => {
-                            let link = export_node
-                                .get()
-                                .expect("The Anchor must exist to preform the file save");
-                            let blob = gloo_file::Blob::new(data.as_slice());
-                            let url = gloo_file::ObjectUrl::from(blob);
-                            link.set_attribute("href", &url).unwrap();
-                            link.set_attribute("download", "exported_life.json")
-                                .unwrap();
-                            // link.query_selector
-                            let click_event: web_sys::Event =
-                                MouseEvent::new("click").unwrap().into();
-                            link.dispatch_event(&click_event).unwrap();
-                        }
-                    };
-                }
-                shared::Effect::Render(_) => set_view.set(core.view()),
-            }
-        }
+        process_effects(&core, effects, shell);
     });
 
     let touch_label = if touch_device {
@@ -590,7 +693,7 @@ fn root_component() -> impl IntoView {
           <ul class="menu-list">
             <li><a>
             <label for="importworld">Import World </label>
-            <input node_ref=input_element class="input is-hidden" id="importworld" type="file"
+            <input node_ref=input_element class="input is-hidden" id="importworld" type="file" accept=".json,.rle,.txt,.rule"
                 on:change=move |ev|{
                     set_show_menu.set(false);
                     let files = ev.target()
@@ -618,16 +721,183 @@ fn root_component() -> impl IntoView {
                 <a>Export World</a>
             </li>
 
+            <li on:click=move |_|{
+                set_event.set(Event::SaveWorldAs(FileFormat::Rle));
+            }>
+                <a>Export World as RLE</a>
+            </li>
+            <li on:click=move |_|{
+                set_event.set(Event::SaveWorldAs(FileFormat::Apgcode));
+            }>
+                <a>Export World as apgcode</a>
+            </li>
+
             <li on:click=move |_|{
                 set_event.set(Event::CopyWorld);
             }>
                 <a>Copy World to clipboard</a>
             </li>
+            <li on:click=move |_|{
+                set_event.set(Event::CopyWorldAs(FileFormat::Rle));
+            }>
+                <a>Copy World as RLE</a>
+            </li>
+            <li on:click=move |_|{
+                set_event.set(Event::CopyWorldAs(FileFormat::Apgcode));
+            }>
+                <a>Copy World as apgcode</a>
+            </li>
+            <li><a>
+            <label for="rule">Rule</label>
+            <input class="input is-small" id="rule" type="text"
+                prop:value=move || view.get().rule
+                on:change=move |ev| set_event.set(Event::SetRule(event_target_value(&ev)))
+                />
+            </a></li>
+            {move || {
+                let chances = view.get().chances;
+                let chance = move |label: &'static str, value: f32, set: fn(Chances, f32) -> Chances| view! {
+                    <li><a>
+                    <label>{label}</label>
+                    <input class="input is-small" type="number" min="0" max="1" step="0.01"
+                        prop:value=value
+                        on:change=move |ev| if let Ok(p) = event_target_value(&ev).parse() {
+                            set_event.set(Event::SetChances(set(chances, p)));
+                        }
+                        />
+                    </a></li>
+                };
+                view! {
+                    {chance("Birth chance", chances.birth, |c, birth| Chances { birth, ..c })}
+                    {chance("Death chance", chances.death, |c, death| Chances { death, ..c })}
+                    {chance("Noise", chances.noise, |c, noise| Chances { noise, ..c })}
+                }
+            }}
+            <li><a>
+            <label for="seed">Seed</label>
+            <input class="input is-small" id="seed" type="number" min="0"
+                prop:value=move || view.get().seed.to_string()
+                on:change=move |ev| if let Ok(seed) = event_target_value(&ev).parse() {
+                    set_event.set(Event::SetSeed(seed));
+                }
+                />
+            </a></li>
+            <li on:click=move |_|{
+                let engine = match view.get().engine {
+                    Engine::Naive => Engine::HashLife,
+                    Engine::HashLife => Engine::Tiles,
+                    Engine::Tiles => Engine::Naive,
+                };
+                set_event.set(Event::SetEngine(engine));
+            }>
+                <a>{move || format!("Engine: {:?}", view.get().engine)}</a>
+            </li>
+            <li><a>
+            <label for="paint">Clicks</label>
+            <select class="select is-small" id="paint"
+                on:change=move |ev| set_event.set(Event::SetPaintState(event_target_value(&ev).parse().ok()))>
+                <option value="toggle" selected=move || view.get().paint_state.is_none()>Toggle cells</option>
+                {move || (0..view.get().states).map(|state| view! {
+                    <option value=state.to_string() selected=move || view.get().paint_state == Some(state)>
+                        {format!("Paint state {state}")}
+                    </option>
+                }).collect_view()}
+            </select>
+            </a></li>
+            <li on:click=move |_| set_event.set(Event::ShowWrappedCopies(!view.get().show_wrapped))>
+                <a>{move || format!("Wrapped copies: {}", if view.get().show_wrapped {"on"} else {"off"})}</a>
+            </li>
+            <li on:click=move |_| set_event.set(Event::Census(census_gap.get()))><a>Census</a></li>
+            <li><a>
+            <label for="gap">Census gap</label>
+            <input class="input is-small" id="gap" type="number" min="0" max="8"
+                prop:value=move || census_gap.get()
+                on:change=move |ev| if let Ok(gap) = event_target_value(&ev).parse() {
+                    set_census_gap.set(gap);
+                }
+                />
+            </a></li>
+            <li on:click=move |_| set_event.set(Event::PauseOnCycle(!view.get().pause_on_cycle))>
+                <a>{move || format!("Pause on cycle: {}", if view.get().pause_on_cycle {"on"} else {"off"})}</a>
+            </li>
+            <li class:is-hidden=move || !view.get().reversible
+                on:click=move |_| set_event.set(Event::RunBackwards(!view.get().backwards))>
+                <a>{move || format!("Direction: {}", if view.get().backwards {"backwards"} else {"forwards"})}</a>
+            </li>
             <li on:click=move |_|{
                 set_show_info.set(true);
                 set_show_menu.set(false);
             }><a>About</a></li>
           </ul>
+          <p class="menu-label">Selection (shift + drag)</p>
+          <ul class="menu-list">
+            <li on:click=move |_| set_event.set(Event::CopySelection)><a>Copy</a></li>
+            <li on:click=move |_| set_event.set(Event::CutSelection)><a>Cut</a></li>
+            <li on:click=move |_| {
+                if let Some([at, _]) = view.get().selection {
+                    set_event.set(Event::Paste(at));
+                }
+            }><a>Paste over selection</a></li>
+            <li on:click=move |_| set_event.set(Event::ClearSelection)><a>Clear</a></li>
+            <li on:click=move |_| {
+                if let Some(rect) = view.get().selection {
+                    set_event.set(Event::RandomFill {
+                        rect,
+                        density: soup_density.get(),
+                        seed: (js_sys::Math::random() * u32::MAX as f64) as u64,
+                        symmetry: soup_symmetry.get(),
+                    });
+                }
+            }><a>Fill with random soup</a></li>
+            <li><a>
+            <label for="density">Soup density</label>
+            <input class="input is-small" id="density" type="number" min="0" max="1" step="0.05"
+                prop:value=move || soup_density.get()
+                on:change=move |ev| if let Ok(density) = event_target_value(&ev).parse() {
+                    set_soup_density.set(density);
+                }
+                />
+            <label for="symmetry">Soup symmetry</label>
+            <select class="select is-small" id="symmetry"
+                on:change=move |ev| {
+                    let symmetry = match event_target_value(&ev).as_str() {
+                        "C2" => Symmetry::C2,
+                        "C4" => Symmetry::C4,
+                        "D2" => Symmetry::D2,
+                        "D4" => Symmetry::D4,
+                        "D8" => Symmetry::D8,
+                        _ => Symmetry::C1,
+                    };
+                    set_soup_symmetry.set(symmetry);
+                }>
+                {["C1", "C2", "C4", "D2", "D4", "D8"].map(|name| view! { <option value=name>{name}</option> })}
+            </select>
+            </a></li>
+            <li on:click=move |_| set_event.set(Event::RotateSelection(Rotation::R90))><a>Rotate clockwise</a></li>
+            <li on:click=move |_| set_event.set(Event::RotateSelection(Rotation::R270))><a>Rotate counterclockwise</a></li>
+            <li on:click=move |_| set_event.set(Event::FlipSelectionRows)><a>Flip vertically</a></li>
+            <li on:click=move |_| set_event.set(Event::FlipSelectionColumns)><a>Flip horizontally</a></li>
+            <li on:click=move |_| set_event.set(Event::Deselect)><a>Deselect</a></li>
+          </ul>
+          <p class="menu-label">Patterns</p>
+          <ul class="menu-list" style="max-height:30vh; overflow-y:auto;">
+            {shared::catalog().iter().map(|pattern| {
+                let name = pattern.name.clone();
+                view! {
+                    <li on:click=move |_| {
+                        set_event.set(Event::PlacePattern {
+                            name: name.clone(),
+                            at: [0, 0],
+                            rotation: Rotation::R0,
+                            mirror: false,
+                        });
+                        set_show_menu.set(false);
+                    }>
+                        <a>{pattern.name.clone()} <span class="is-size-7">{format!(" {:?}", pattern.category)}</span></a>
+                    </li>
+                }
+            }).collect_view()}
+          </ul>
         </aside>
         </>
     };
@@ -655,23 +925,72 @@ fn root_component() -> impl IntoView {
     <div/> // spacer
         <div class="buttons is-centered mb-5 is-flex" style="position:absolute; bottom: 5dvh; justify-content: center; width:100%;">
             <button class="button is-success" class:is-danger=running
-            on:click=move |_| set_run.update(|state| *state = !*state)>
-                {move || if running.get() {"Stop"} else {"Run"}}
+            on:click=move |_| set_event.set(if running() {Event::Pause} else {Event::Run})>
+                {move || if running() {"Stop"} else {"Run"}}
             </button>
             <button class="button is-warning"
-                on:click=move |_| {
-                    set_run.update(|state| *state = false);
-                    set_event.update(|value| *value = Event::Step);
-                }>
+                on:click=move |_| set_event.set(Event::Step)>
                 "Step"
             </button>
+            <button class="button is-warning"
+                on:click=move |_| set_event.set(Event::StepPow(10))>
+                "Jump 1024"
+            </button>
+            <button class="button is-light" disabled=move || !view.get().can_step_back
+                on:click=move |_| set_event.set(Event::StepBack)>
+                "Back"
+            </button>
+            <button class="button is-light" disabled=move || !view.get().can_undo
+                on:click=move |_| set_event.set(Event::Undo)>
+                "Undo"
+            </button>
+            <button class="button is-light" disabled=move || !view.get().can_redo
+                on:click=move |_| set_event.set(Event::Redo)>
+                "Redo"
+            </button>
             <p>""</p>
         </div>
+        <div class="has-text-light is-size-7" style="position:absolute; top: 2dvh; right: 2em; z-index:2; text-align:right;">
+            <p>{move || format!("generation {}", view.get().generation)}</p>
+            <p>{move || {
+                let view = view.get();
+                format!("population {} (+{} -{})", view.population, view.births, view.deaths)
+            }}</p>
+            <p>{move || view.get().cycle.map(|cycle| {
+                let [rows, cols] = cycle.displacement;
+                match [rows, cols] {
+                    [0, 0] => format!("stabilized at generation {} with period {}", cycle.generation, cycle.period),
+                    _ => format!("stabilized at generation {} with period {} moving ({rows}, {cols})", cycle.generation, cycle.period),
+                }
+            })}</p>
+            <svg width="128" height="32" viewBox="0 0 128 32" preserveAspectRatio="none">
+                <polyline fill="none" stroke="hsl(171, 100%, 41%)" points=move || population_points(&view.get().population_history) />
+            </svg>
+            <p>{move || view.get().census.map(|census| format!("census at generation {}: {census}", census.generation))}</p>
+        </div>
 
     </section>
     </main>}
 }
 
+/// svg polyline points of the population history scaled to a 128x32 box
+fn population_points(history: &[u64]) -> String {
+    let max = history.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let step = 128.0 / history.len().max(2).saturating_sub(1) as f64;
+    history
+        .iter()
+        .enumerate()
+        .map(|(i, pop)| {
+            format!(
+                "{:.1},{:.1}",
+                i as f64 * step,
+                32.0 - 32.0 * *pop as f64 / max
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 fn main() {
     _ = console_log::init_with_level(log::Level::Debug);
     console_error_panic_hook::set_once();